@@ -1,8 +1,13 @@
 extern crate chrono;
 
 use chrono::prelude::*;
+use chrono_tz::Tz;
 use log::warn;
 use regex::{Captures, Regex};
+use std::sync::LazyLock;
+
+use crate::recurrence::Recurrence;
+use crate::timezone::{local_to_utc, parse_zone_suffix};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Command {
@@ -14,141 +19,1212 @@ pub enum Command {
 pub struct OneTimeEventImpl {
     pub event_time: DateTime<Utc>,
     pub event_text: String,
+    /// Zone written in the command instead of the user's one, `at 10 UTC`.
+    /// Only shown in the confirmation, the time itself is absolute.
+    pub event_timezone: Option<Tz>,
+    /// Alerts sent this long before the event, `at 14 -15m standup`.
+    pub event_leads: Vec<chrono::Duration>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct RepetitiveEventImpl {
     pub event_start_time: DateTime<Utc>,
-    pub event_wait_time: chrono::Duration,
+    pub event_rule: Recurrence,
     pub event_text: String,
+    /// Series ends before this moment.
+    pub event_until: Option<DateTime<Utc>>,
+    /// Occurrences left, including the nearest one.
+    pub event_count: Option<u32>,
+    /// Zone the rule is followed in, `None` for the user's zone.
+    pub event_timezone: Option<Tz>,
+    /// Alerts sent this long before every occurrence.
+    pub event_leads: Vec<chrono::Duration>,
+}
+
+impl RepetitiveEventImpl {
+    /// Up to `limit` nearest occurrences not earlier than `now`, the way
+    /// they will fire, i.e. with the series end applied.
+    pub fn next_occurrences(
+        &self,
+        tz: &Tz,
+        now: DateTime<Utc>,
+        limit: usize,
+    ) -> Vec<DateTime<Utc>> {
+        self.occurrences_from(tz, now).take(limit).collect()
+    }
+
+    /// Occurrences not earlier than `now` in order, till the series end.
+    pub fn occurrences_from<'a>(
+        &'a self,
+        tz: &'a Tz,
+        now: DateTime<Utc>,
+    ) -> impl Iterator<Item = DateTime<Utc>> + 'a {
+        let limit = self.event_count.map_or(usize::MAX, |count| count as usize);
+        let mut after = now;
+        std::iter::from_fn(move || {
+            let next = self
+                .event_rule
+                .next_occurrence(self.event_start_time, tz, after);
+            after = next + chrono::Duration::seconds(1);
+            Some(next)
+        })
+        .take(limit)
+        .take_while(|next| self.event_until.is_none_or(|until| *next < until))
+    }
+}
+
+/// Why a line is not a reminder. `NoMatch` means it doesn't look like one at
+/// all, other variants point at the part of the time spec to fix.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    NoMatch,
+    InvalidNumber(String),
+    InvalidMonth {
+        day: u32,
+        month: u32,
+        order: DateOrder,
+    },
+    InvalidDay {
+        day: u32,
+        month: u32,
+        year: i32,
+        order: DateOrder,
+    },
+    HourOutOfRange {
+        hour: u32,
+        am_pm: bool,
+    },
+    MinuteOutOfRange(u32),
+    InvalidOffset(String),
+    MissingText,
+    UnknownUnit(String),
+    ZeroDuration,
+    UnknownRule(String),
+    UnknownWeekday(String),
+    InvalidCron(String),
+    ZeroCount,
+    EndsBeforeStart,
+    InvalidLead(String),
+}
+
+/// For logs, users get `Lang::parse_error` in their language.
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// How numeric dates like `03-04` are read, a per-user preference.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DateOrder {
+    /// `31-12`
+    #[default]
+    DayMonth,
+    /// `12-31`
+    MonthDay,
+}
+
+pub const DATE_ORDERS: &[DateOrder] = &[DateOrder::DayMonth, DateOrder::MonthDay];
+
+impl DateOrder {
+    pub fn code(self) -> &'static str {
+        match self {
+            DateOrder::DayMonth => "dmy",
+            DateOrder::MonthDay => "mdy",
+        }
+    }
+
+    pub fn from_code(code: Option<&str>) -> DateOrder {
+        match code {
+            Some("mdy") => DateOrder::MonthDay,
+            _ => DateOrder::DayMonth,
+        }
+    }
+
+    /// Day and month of a date written as `first-second`.
+    fn day_month(self, first: u32, second: u32) -> (u32, u32) {
+        match self {
+            DateOrder::DayMonth => (first, second),
+            DateOrder::MonthDay => (second, first),
+        }
+    }
+
+    /// `31-12` or `12-31`, the way the parser reads it back.
+    pub fn format(self, day: u32, month: u32) -> String {
+        match self {
+            DateOrder::DayMonth => format!("{day:02}-{month:02}"),
+            DateOrder::MonthDay => format!("{month:02}-{day:02}"),
+        }
+    }
+}
+
+/// Words of one language known to the parser. Grammar regexes are built
+/// from all packs together, so a line may mix languages.
+pub struct LanguagePack {
+    /// `at` in `10-11 at 12.30`.
+    pub at: &'static [&'static str],
+    /// Optional word before a weekday, `on friday`.
+    pub on: &'static [&'static str],
+    pub today: &'static [&'static str],
+    pub tomorrow: &'static [&'static str],
+    pub day_after_tomorrow: &'static [&'static str],
+    /// Suffixes of 12-hour clock times, `7pm`.
+    pub am: &'static [&'static str],
+    pub pm: &'static [&'static str],
+    /// Starts a repetitive event, `rep 10.00 1d`.
+    pub repeat: &'static [&'static str],
+    /// Starts a weekday rule, `every mon at 9`.
+    pub every: &'static [&'static str],
+    pub cron: &'static [&'static str],
+    /// Series end date, `until 31-12`.
+    pub until: &'static [&'static str],
+    /// Prefix of the occurrence count, `x10`.
+    pub times: &'static [&'static str],
+    /// Last day or weekday of a month, `last`, `last-fri`.
+    pub last: &'static [&'static str],
+    /// Word before a duration in free text, `call mom in 2 hours`.
+    pub after: &'static [&'static str],
+    /// Duration units with their length in months and seconds.
+    pub units: &'static [(&'static str, (u32, i64))],
+    /// Units that may stand apart from the number, `2 hours`. Short ones
+    /// are left out, `5 с Машей` or `2 min read` is just text.
+    pub spaced_units: &'static [&'static str],
+    pub weekdays: &'static [(&'static str, Weekday)],
+    pub weekday_groups: &'static [(&'static str, &'static [Weekday])],
+    pub months: &'static [(&'static str, u32)],
+}
+
+const SECONDS_IN_DAY: i64 = 60 * 60 * 24;
+const SECOND: (u32, i64) = (0, 1);
+const MINUTE: (u32, i64) = (0, 60);
+const HOUR: (u32, i64) = (0, 60 * 60);
+const DAY: (u32, i64) = (0, SECONDS_IN_DAY);
+const WEEK: (u32, i64) = (0, 7 * SECONDS_IN_DAY);
+const MONTH: (u32, i64) = (1, 0);
+const YEAR: (u32, i64) = (12, 0);
+
+pub const ENGLISH: LanguagePack = LanguagePack {
+    at: &["at"],
+    on: &["on"],
+    today: &["today"],
+    tomorrow: &["tomorrow"],
+    day_after_tomorrow: &["day after tomorrow"],
+    am: &["am"],
+    pm: &["pm"],
+    repeat: &["rep", "repeat"],
+    every: &["every"],
+    cron: &["cron"],
+    until: &["until"],
+    times: &["x"],
+    last: &["last"],
+    after: &["in"],
+    units: &[
+        ("s", SECOND),
+        ("sec", SECOND),
+        ("secs", SECOND),
+        ("second", SECOND),
+        ("seconds", SECOND),
+        ("m", MINUTE),
+        ("min", MINUTE),
+        ("mins", MINUTE),
+        ("minute", MINUTE),
+        ("minutes", MINUTE),
+        ("h", HOUR),
+        ("hr", HOUR),
+        ("hrs", HOUR),
+        ("hour", HOUR),
+        ("hours", HOUR),
+        ("d", DAY),
+        ("day", DAY),
+        ("days", DAY),
+        ("w", WEEK),
+        ("wk", WEEK),
+        ("week", WEEK),
+        ("weeks", WEEK),
+        ("mo", MONTH),
+        ("month", MONTH),
+        ("months", MONTH),
+        ("y", YEAR),
+        ("yr", YEAR),
+        ("year", YEAR),
+        ("years", YEAR),
+    ],
+    spaced_units: &[
+        "second", "seconds", "minute", "minutes", "hour", "hours", "day", "days", "week", "weeks",
+        "month", "months", "year", "years",
+    ],
+    weekdays: &[
+        ("mon", Weekday::Mon),
+        ("tue", Weekday::Tue),
+        ("wed", Weekday::Wed),
+        ("thu", Weekday::Thu),
+        ("fri", Weekday::Fri),
+        ("sat", Weekday::Sat),
+        ("sun", Weekday::Sun),
+        ("monday", Weekday::Mon),
+        ("tuesday", Weekday::Tue),
+        ("wednesday", Weekday::Wed),
+        ("thursday", Weekday::Thu),
+        ("friday", Weekday::Fri),
+        ("saturday", Weekday::Sat),
+        ("sunday", Weekday::Sun),
+    ],
+    weekday_groups: &[
+        ("day", &ALL_WEEKDAYS),
+        ("weekday", &WORKING_DAYS),
+        ("weekend", &WEEKEND),
+    ],
+    months: &[
+        ("jan", 1),
+        ("january", 1),
+        ("feb", 2),
+        ("february", 2),
+        ("mar", 3),
+        ("march", 3),
+        ("apr", 4),
+        ("april", 4),
+        ("may", 5),
+        ("jun", 6),
+        ("june", 6),
+        ("jul", 7),
+        ("july", 7),
+        ("aug", 8),
+        ("august", 8),
+        ("sep", 9),
+        ("sept", 9),
+        ("september", 9),
+        ("oct", 10),
+        ("october", 10),
+        ("nov", 11),
+        ("november", 11),
+        ("dec", 12),
+        ("december", 12),
+    ],
+};
+
+pub const RUSSIAN: LanguagePack = LanguagePack {
+    at: &["в"],
+    on: &["в", "во"],
+    today: &["сегодня"],
+    tomorrow: &["завтра"],
+    day_after_tomorrow: &["послезавтра"],
+    am: &["утра"],
+    pm: &["дня", "вечера"],
+    repeat: &["повтор", "повторять"],
+    every: &["каждый", "каждую", "каждое", "каждая", "каждые"],
+    cron: &["крон"],
+    until: &["до"],
+    times: &["х"],
+    last: &["посл"],
+    after: &["через"],
+    units: &[
+        ("с", SECOND),
+        ("сек", SECOND),
+        ("секунда", SECOND),
+        ("секунды", SECOND),
+        ("секунд", SECOND),
+        ("секунду", SECOND),
+        ("м", MINUTE),
+        ("мин", MINUTE),
+        ("минута", MINUTE),
+        ("минуты", MINUTE),
+        ("минут", MINUTE),
+        ("минуту", MINUTE),
+        ("ч", HOUR),
+        ("час", HOUR),
+        ("часа", HOUR),
+        ("часов", HOUR),
+        ("д", DAY),
+        ("дн", DAY),
+        ("день", DAY),
+        ("дня", DAY),
+        ("дней", DAY),
+        ("н", WEEK),
+        ("нед", WEEK),
+        ("неделя", WEEK),
+        ("недели", WEEK),
+        ("недель", WEEK),
+        ("неделю", WEEK),
+        ("мес", MONTH),
+        ("месяц", MONTH),
+        ("месяца", MONTH),
+        ("месяцев", MONTH),
+        ("г", YEAR),
+        ("год", YEAR),
+        ("года", YEAR),
+        ("лет", YEAR),
+    ],
+    spaced_units: &[
+        "секунда",
+        "секунды",
+        "секунд",
+        "секунду",
+        "минута",
+        "минуты",
+        "минут",
+        "минуту",
+        "час",
+        "часа",
+        "часов",
+        "день",
+        "дня",
+        "дней",
+        "неделя",
+        "недели",
+        "недель",
+        "неделю",
+        "месяц",
+        "месяца",
+        "месяцев",
+        "год",
+        "года",
+        "лет",
+    ],
+    weekdays: &[
+        ("пн", Weekday::Mon),
+        ("вт", Weekday::Tue),
+        ("ср", Weekday::Wed),
+        ("чт", Weekday::Thu),
+        ("пт", Weekday::Fri),
+        ("сб", Weekday::Sat),
+        ("вс", Weekday::Sun),
+        ("понедельник", Weekday::Mon),
+        ("вторник", Weekday::Tue),
+        ("среда", Weekday::Wed),
+        ("среду", Weekday::Wed),
+        ("четверг", Weekday::Thu),
+        ("пятница", Weekday::Fri),
+        ("пятницу", Weekday::Fri),
+        ("суббота", Weekday::Sat),
+        ("субботу", Weekday::Sat),
+        ("воскресенье", Weekday::Sun),
+    ],
+    weekday_groups: &[
+        ("день", &ALL_WEEKDAYS),
+        ("будни", &WORKING_DAYS),
+        ("выходные", &WEEKEND),
+    ],
+    months: &[
+        ("янв", 1),
+        ("января", 1),
+        ("фев", 2),
+        ("февраля", 2),
+        ("мар", 3),
+        ("марта", 3),
+        ("апр", 4),
+        ("апреля", 4),
+        ("мая", 5),
+        ("июн", 6),
+        ("июня", 6),
+        ("июл", 7),
+        ("июля", 7),
+        ("авг", 8),
+        ("августа", 8),
+        ("сен", 9),
+        ("сентября", 9),
+        ("окт", 10),
+        ("октября", 10),
+        ("ноя", 11),
+        ("ноября", 11),
+        ("дек", 12),
+        ("декабря", 12),
+    ],
+};
+
+pub const LANGUAGE_PACKS: &[&LanguagePack] = &[&ENGLISH, &RUSSIAN];
+
+const WORKING_DAYS: [Weekday; 5] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+];
+
+const WEEKEND: [Weekday; 2] = [Weekday::Sat, Weekday::Sun];
+
+const ALL_WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// Case-insensitive alternation of `words` from every pack, longest first.
+fn words_regex(words: impl Fn(&LanguagePack) -> Vec<&'static str>) -> String {
+    let mut all: Vec<&str> = LANGUAGE_PACKS.iter().flat_map(|pack| words(pack)).collect();
+    all.sort_by_key(|word| std::cmp::Reverse(word.chars().count()));
+    all.dedup();
+    let all: Vec<String> = all.iter().map(|word| regex::escape(word)).collect();
+    format!("(?i:{})", all.join("|"))
 }
 
-const MOMENT_DAY_REGEX: &str =
-    r"(?:(?P<m_day>[\d]+))?(?:-(?P<m_month>[\d]+))?(?:-(?P<m_year>[\d]+))?";
+fn names<T>(table: &'static [(&'static str, T)]) -> Vec<&'static str> {
+    table.iter().map(|(name, _)| *name).collect()
+}
+
+/// Value of the (lowercase) `word` in the `table` of any pack.
+fn find_word<T: Copy + 'static>(
+    word: &str,
+    table: impl Fn(&LanguagePack) -> &'static [(&'static str, T)],
+) -> Option<T> {
+    LANGUAGE_PACKS
+        .iter()
+        .flat_map(|pack| table(pack))
+        .find(|(name, _)| *name == word)
+        .map(|(_, value)| *value)
+}
+
+pub(crate) fn is_word(
+    word: &str,
+    words: impl Fn(&LanguagePack) -> &'static [&'static str],
+) -> bool {
+    LANGUAGE_PACKS
+        .iter()
+        .any(|pack| words(pack).contains(&word))
+}
+
+/// `10`, `10-11`, `10-11-2026` or `10 nov`.
+fn moment_day_regex() -> String {
+    format!(
+        r"(?:(?P<m_day>[\d]+))?(?:-(?P<m_month>[\d]+)|\s+(?P<m_month_name>{}))?(?:-(?P<m_year>[\d]+))?",
+        words_regex(|pack| names(pack.months))
+    )
+}
+
+/// `18`, `18.30`, `6:30 pm`.
+fn moment_time_regex() -> String {
+    format!(
+        r"(?P<m_hour>[\d]+)(?:[.|:](?P<m_minute>[\d]+))?(?:\s*(?P<m_ampm>{}))?",
+        words_regex(|pack| [pack.am, pack.pm].concat())
+    )
+}
+
+// event text, missing text is reported instead of not matching
+const TEXT_REGEX: &str = r"(?:\s+(?P<main_text>.*))?$";
 
-const MOMENT_TIME_REGEX: &str = r"(?P<m_hour>[\d]+)(?:[.|:](?P<m_minute>[\d]+))?";
+// 2026-11-03T14:00, seconds and offset are optional
+const ISO_DATETIME_REGEX: &str = r"^(?P<iso_time>\d{4}-\d{2}-\d{2}T\d{2}:\d{2}(?::\d{2}(?:\.\d+)?)?)(?P<iso_offset>Z|[+-]\d{2}(?::?\d{2})?)?";
 
-const DURATION_REGEX: &str = r"(:?(?P<d_day>[\d]*)[D|d|Д|д])?(:?(?P<d_hour>[\d]*)[H|h|Ч|ч])?(:?(?P<d_minute>[\d]*)[M|m|М|м])?(:?(?P<d_second>[\d]*)[S|s|С|с])?";
+// number and unit, `2h`, `30 minutes`, `1 день`
+const DURATION_PART_REGEX: &str = r"^\s*(?P<number>\d+)(?P<space>\s*)(?P<unit>\p{L}+)";
+
+// lead alert before the event, `-15m`, `-1h30m`
+const LEAD_REGEX: &str = r"^-(?:\d+\p{L}+)+$";
+
+type Parser<'a> = &'a dyn Fn(&str) -> Result<Command, ParseError>;
 
 pub fn parse_command(
     command_line: String,
     now: DateTime<Utc>,
-    user_timezone: i32,
-) -> Option<Command> {
-    let command_line = String::from(command_line.trim());
-    let mut result;
-    result = try_parse_for(&command_line, now);
-    if result.is_some() {
-        return result;
-    }
+    user_timezone: Tz,
+    date_order: DateOrder,
+) -> Result<Command, ParseError> {
+    let command_line = command_line.trim();
+    let parsers: [Parser; 7] = [
+        &|line| try_parse_for(line, now, &user_timezone),
+        &|line| try_parse_iso(line, &user_timezone, date_order),
+        &|line| try_parse_at(line, now, &user_timezone, date_order),
+        &|line| try_parse_relative_at(line, now, &user_timezone),
+        &|line| try_parse_rep(line, now, &user_timezone, date_order),
+        &|line| try_parse_every(line, now, &user_timezone, date_order),
+        &|line| try_parse_cron(line, now, &user_timezone, date_order),
+    ];
 
-    result = try_parse_at(&command_line, now, user_timezone);
-    if result.is_some() {
-        return result;
+    // the first grammar that recognized the line explains what is wrong
+    let mut error = ParseError::NoMatch;
+    for parse in parsers {
+        match parse(command_line) {
+            Ok(command) => return take_leads(command),
+            Err(ParseError::NoMatch) => {}
+            Err(e) if error == ParseError::NoMatch => error = e,
+            Err(_) => {}
+        }
     }
 
-    result = try_parse_rep(&command_line, now, user_timezone);
-    if result.is_some() {
-        return result;
-    }
+    warn!("parse_command: cannot parse line {command_line}: {error}");
+    Err(error)
+}
+
+fn try_parse_at(
+    command_line: &str,
+    now: DateTime<Utc>,
+    user_timezone: &Tz,
+    date_order: DateOrder,
+) -> Result<Command, ParseError> {
+    static REG: LazyLock<Regex> = LazyLock::new(|| {
+        let reg = format!(
+            r"^{}\s*{}\s*{}{}",
+            moment_day_regex(),
+            words_regex(|pack| pack.at.to_vec()),
+            moment_time_regex(),
+            TEXT_REGEX
+        );
+        Regex::new(&reg).unwrap()
+    });
+
+    let date_captures = REG.captures(command_line).ok_or(ParseError::NoMatch)?;
+    let (zone, text) = get_zone_and_text_from_capture(&date_captures, now)?;
+    let tz = zone.unwrap_or(*user_timezone);
+    let event_time = get_datetime_from_capture(&date_captures, now, &tz, date_order)?;
 
-    warn!(
-        "parse_command: line {} doesn't match any regex",
-        command_line
-    );
-    None
+    Ok(Command::OneTimeEvent(OneTimeEventImpl {
+        event_text: String::from(text),
+        event_time,
+        event_timezone: zone,
+        event_leads: Vec::new(),
+    }))
 }
 
-fn try_parse_at(command_line: &str, now: DateTime<Utc>, user_timezone: i32) -> Option<Command> {
-    let reg = format!(
-        r"^{}\s*(at|At|в|В)\s*{} (?P<main_text>.*)",
-        MOMENT_DAY_REGEX, MOMENT_TIME_REGEX
-    );
-    let time_format = Regex::new(&reg[..]).unwrap();
+/// ISO-8601 date and time, in the user's time zone unless the offset is given.
+fn try_parse_iso(
+    command_line: &str,
+    user_timezone: &Tz,
+    date_order: DateOrder,
+) -> Result<Command, ParseError> {
+    static REG: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(&format!("{}{}", ISO_DATETIME_REGEX, TEXT_REGEX)).unwrap());
+
+    let capture = REG.captures(command_line).ok_or(ParseError::NoMatch)?;
+    let iso_time = &capture["iso_time"];
+    let time = NaiveDateTime::parse_from_str(iso_time, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(iso_time, "%Y-%m-%dT%H:%M"))
+        .map_err(|_| check_date_time(iso_time, date_order))?;
+    let event_time = match capture.name("iso_offset").map(|c| c.as_str()) {
+        None => local_to_utc(user_timezone, time),
+        Some("Z") => time.and_utc(),
+        Some(offset) => {
+            let invalid = || ParseError::InvalidOffset(offset.to_owned());
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            let digits = offset[1..].replace(':', "");
+            let hours: i32 = digits[..2].parse().map_err(|_| invalid())?;
+            let minutes: i32 = match &digits[2..] {
+                "" => 0,
+                minutes => minutes.parse().map_err(|_| invalid())?,
+            };
+            let offset = FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+                .filter(|_| minutes < 60)
+                .ok_or_else(invalid)?;
+            offset
+                .from_local_datetime(&time)
+                .single()
+                .ok_or_else(invalid)?
+                .to_utc()
+        }
+    };
+    let text = get_text_from_capture(&capture)?;
 
-    let date_captures = time_format.captures(command_line)?;
-    let text = date_captures.name("main_text").unwrap().as_str();
+    Ok(Command::OneTimeEvent(OneTimeEventImpl {
+        event_text: String::from(text),
+        event_time,
+        event_timezone: None,
+        event_leads: Vec::new(),
+    }))
+}
 
-    if let Some(t) = get_datetime_from_capture(&date_captures, now, user_timezone) {
-        return Some(Command::OneTimeEvent(OneTimeEventImpl {
-            event_text: String::from(text),
-            event_time: t,
-        }));
+/// Finds which part of an ISO datetime rejected by chrono is wrong, hints
+/// show the date in `order`.
+fn check_date_time(iso_time: &str, order: DateOrder) -> ParseError {
+    let numbers: Vec<u32> = iso_time
+        .split(['-', 'T', ':', '.'])
+        .map(|n| n.parse().unwrap_or(u32::MAX))
+        .collect();
+    let (year, month, day) = (numbers[0] as i32, numbers[1], numbers[2]);
+    if let Err(e) = check_date(year, month, day, order) {
+        return e;
+    }
+    match (numbers[3], numbers[4]) {
+        (hour, _) if hour > 23 => ParseError::HourOutOfRange { hour, am_pm: false },
+        (_, minute) if minute > 59 => ParseError::MinuteOutOfRange(minute),
+        _ => ParseError::NoMatch,
     }
-    None
 }
 
-fn try_parse_for(command_line: &str, now: DateTime<Utc>) -> Option<Command> {
-    let reg = String::from("^") + DURATION_REGEX + r"(?P<divider> )(?P<main_text>.*)";
-    let reg = Regex::new(&reg[..]).unwrap();
+/// `tomorrow at 9`, `в пятницу в 18`: the day is counted from the user's
+/// local date, a weekday whose time has passed today means the next week.
+fn try_parse_relative_at(
+    command_line: &str,
+    now: DateTime<Utc>,
+    user_timezone: &Tz,
+) -> Result<Command, ParseError> {
+    static REG: LazyLock<Regex> = LazyLock::new(|| {
+        let day_words =
+            words_regex(|pack| [pack.today, pack.tomorrow, pack.day_after_tomorrow].concat());
+        let reg = format!(
+            r"^(?:{}\s+)?(?P<rel_day>{}|\S+)\s+{}\s*{}{}",
+            words_regex(|pack| pack.on.to_vec()),
+            day_words,
+            words_regex(|pack| pack.at.to_vec()),
+            moment_time_regex(),
+            TEXT_REGEX
+        );
+        Regex::new(&reg).unwrap()
+    });
 
-    let capture = reg.captures(command_line)?;
+    let capture = REG.captures(command_line).ok_or(ParseError::NoMatch)?;
+    let (zone, text) = get_zone_and_text_from_capture(&capture, now)?;
+    let user_timezone = &zone.unwrap_or(*user_timezone);
+    let today = now.with_timezone(user_timezone).date_naive();
+    let rel_day = capture["rel_day"].to_lowercase();
+    let offset = if is_word(&rel_day, |pack| pack.today) {
+        Some(0)
+    } else if is_word(&rel_day, |pack| pack.tomorrow) {
+        Some(1)
+    } else if is_word(&rel_day, |pack| pack.day_after_tomorrow) {
+        Some(2)
+    } else {
+        None
+    };
+    let weekday = match offset {
+        Some(_) => None,
+        None => Some(parse_weekday(&rel_day).ok_or(ParseError::NoMatch)?),
+    };
+    let time = get_time_from_capture(&capture)?;
+    let offset = offset.unwrap_or_else(|| {
+        let weekday = weekday.unwrap();
+        let offset =
+            (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+        if offset == 0 && local_to_utc(user_timezone, today.and_time(time)) < now {
+            7
+        } else {
+            offset as i64
+        }
+    });
+    let date = today + chrono::Duration::days(offset);
+
+    Ok(Command::OneTimeEvent(OneTimeEventImpl {
+        event_text: String::from(text),
+        event_time: local_to_utc(user_timezone, date.and_time(time)),
+        event_timezone: zone,
+        event_leads: Vec::new(),
+    }))
+}
 
-    let text = capture.name("main_text").unwrap().as_str();
-    let dt = get_duration_from_capture(&capture)?;
+fn try_parse_for(
+    command_line: &str,
+    now: DateTime<Utc>,
+    user_timezone: &Tz,
+) -> Result<Command, ParseError> {
+    let (duration, text) = parse_duration(command_line)?;
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(ParseError::MissingText);
+    }
 
-    Some(Command::OneTimeEvent(OneTimeEventImpl {
+    Ok(Command::OneTimeEvent(OneTimeEventImpl {
         event_text: String::from(text),
-        event_time: now + dt,
+        event_time: duration.after(now, user_timezone),
+        event_timezone: None,
+        event_leads: Vec::new(),
     }))
 }
 
-fn try_parse_rep(command_line: &str, now: DateTime<Utc>, user_timezone: i32) -> Option<Command> {
-    let reg = format!(
-        r"^rep\s*{}\s+{}\s+{}(?P<divider> )(?P<main_text>.*)",
-        MOMENT_DAY_REGEX, MOMENT_TIME_REGEX, DURATION_REGEX
-    );
-    let reg = Regex::new(&reg[..]).unwrap();
+fn try_parse_rep(
+    command_line: &str,
+    now: DateTime<Utc>,
+    user_timezone: &Tz,
+    date_order: DateOrder,
+) -> Result<Command, ParseError> {
+    static REG: LazyLock<Regex> = LazyLock::new(|| {
+        let reg = format!(
+            r"^{}\s*{}\s+{}\s+(?P<rule>\S+){}",
+            words_regex(|pack| pack.repeat.to_vec()),
+            moment_day_regex(),
+            moment_time_regex(),
+            TEXT_REGEX
+        );
+        Regex::new(&reg).unwrap()
+    });
+    static TIME_ONLY_REG: LazyLock<Regex> = LazyLock::new(|| {
+        let reg = format!(
+            r"^{}\s*{}\s+(?P<rule>\S+){}",
+            words_regex(|pack| pack.repeat.to_vec()),
+            moment_time_regex(),
+            TEXT_REGEX
+        );
+        Regex::new(&reg).unwrap()
+    });
 
-    let capture = reg.captures(command_line)?;
-    let text = capture.name("main_text").unwrap().as_str();
-    let time = get_datetime_from_capture(&capture, now, user_timezone);
-    let dt = get_duration_from_capture(&capture);
-    if time.is_none() || dt.is_none() {
-        return None;
+    let capture = REG.captures(command_line).ok_or(ParseError::NoMatch)?;
+    let result = rep_from_capture(command_line, &capture, now, user_timezone, date_order);
+    if !matches!(result, Err(ParseError::UnknownRule(_))) {
+        return result;
+    }
+
+    // `rep 9 1 week tea` is 9.00 every week, not 1.00 on the 9th every `week`
+    match TIME_ONLY_REG
+        .captures(command_line)
+        .map(|capture| rep_from_capture(command_line, &capture, now, user_timezone, date_order))
+    {
+        Some(Err(ParseError::UnknownRule(_))) | None => result,
+        Some(time_only) => time_only,
+    }
+}
+
+fn rep_from_capture(
+    command_line: &str,
+    capture: &Captures,
+    now: DateTime<Utc>,
+    user_timezone: &Tz,
+    date_order: DateOrder,
+) -> Result<Command, ParseError> {
+    let rest = &command_line[capture.name("rule").unwrap().start()..];
+    // `rep 9 UTC 1d tea`: the zone takes the place of the rule
+    let (zone, rest) = match parse_zone_suffix(&capture["rule"], now) {
+        Some(zone) => (Some(zone), rest[capture["rule"].len()..].trim()),
+        None => (None, rest),
+    };
+    if rest.is_empty() {
+        return Err(ParseError::MissingText);
+    }
+    let tz = zone.unwrap_or(*user_timezone);
+    let time = get_datetime_from_capture(capture, now, &tz, date_order)?;
+    let (rule, text) = take_recurrence(rest)?;
+    if text.is_empty() {
+        return Err(ParseError::MissingText);
+    }
+
+    make_repetitive_event(time, rule, text, now, &tz, zone, date_order)
+}
+
+fn try_parse_every(
+    command_line: &str,
+    now: DateTime<Utc>,
+    user_timezone: &Tz,
+    date_order: DateOrder,
+) -> Result<Command, ParseError> {
+    static REG: LazyLock<Regex> = LazyLock::new(|| {
+        let reg = format!(
+            r"^{}\s+(?P<days>[^\s,]+(?:\s*,\s*[^\s,]+)*)\s+{}\s+{}{}",
+            words_regex(|pack| pack.every.to_vec()),
+            words_regex(|pack| pack.at.to_vec()),
+            moment_time_regex(),
+            TEXT_REGEX
+        );
+        Regex::new(&reg).unwrap()
+    });
+
+    let capture = REG.captures(command_line).ok_or(ParseError::NoMatch)?;
+    let days = parse_weekday_set(&capture["days"].to_lowercase())?;
+    let (zone, text) = get_zone_and_text_from_capture(&capture, now)?;
+    let tz = zone.unwrap_or(*user_timezone);
+    let time = get_datetime_from_capture(&capture, now, &tz, date_order)?;
+
+    make_repetitive_event(
+        time,
+        Recurrence::Weekdays(days),
+        text,
+        now,
+        &tz,
+        zone,
+        date_order,
+    )
+}
+
+fn try_parse_cron(
+    command_line: &str,
+    now: DateTime<Utc>,
+    user_timezone: &Tz,
+    date_order: DateOrder,
+) -> Result<Command, ParseError> {
+    static REG: LazyLock<Regex> = LazyLock::new(|| {
+        let reg = format!(
+            r#"^{}\s+"(?P<rule>[^"]+)"{}"#,
+            words_regex(|pack| pack.cron.to_vec()),
+            TEXT_REGEX
+        );
+        Regex::new(&reg).unwrap()
+    });
+
+    let capture = REG.captures(command_line).ok_or(ParseError::NoMatch)?;
+    let rule = Recurrence::cron(&capture["rule"])
+        .ok_or_else(|| ParseError::InvalidCron(capture["rule"].to_owned()))?;
+    let start = rule.next_occurrence(now, user_timezone, now);
+    let text = get_text_from_capture(&capture)?;
+
+    make_repetitive_event(start, rule, text, now, user_timezone, None, date_order)
+}
+
+/// Takes the optional series end (`until 31-12`, `x10`, in any order) from
+/// the start of `text`. Series ending before the first occurrence are rejected.
+/// `tz` is the zone the rule is followed in, `zone` when written in the command.
+fn make_repetitive_event(
+    start: DateTime<Utc>,
+    rule: Recurrence,
+    text: &str,
+    now: DateTime<Utc>,
+    tz: &Tz,
+    zone: Option<Tz>,
+    date_order: DateOrder,
+) -> Result<Command, ParseError> {
+    static UNTIL_REG: LazyLock<Regex> = LazyLock::new(|| {
+        let reg = format!(
+            r"^{}\s+(?P<e_day>\d+)-(?P<e_month>\d+)(?:-(?P<e_year>\d+))?\s+(?P<rest>.*)",
+            words_regex(|pack| pack.until.to_vec())
+        );
+        Regex::new(&reg).unwrap()
+    });
+    static COUNT_REG: LazyLock<Regex> = LazyLock::new(|| {
+        let reg = format!(
+            r"^{}(?P<e_count>\d+)\s+(?P<rest>.*)",
+            words_regex(|pack| pack.times.to_vec())
+        );
+        Regex::new(&reg).unwrap()
+    });
+    let mut until = None;
+    let mut count = None;
+    let mut text = text;
+    loop {
+        if let Some(cap) = UNTIL_REG.captures(text).filter(|_| until.is_none()) {
+            until = Some(get_series_until(&cap, now, tz, date_order)?);
+            text = cap.name("rest").unwrap().as_str();
+        } else if let Some(cap) = COUNT_REG.captures(text).filter(|_| count.is_none()) {
+            count = Some(parse_number(&cap["e_count"])?);
+            text = cap.name("rest").unwrap().as_str();
+        } else {
+            break;
+        }
+    }
+    if count == Some(0) {
+        return Err(ParseError::ZeroCount);
+    }
+    if let Some(until) = until
+        && rule.next_occurrence(start, tz, now) >= until
+    {
+        return Err(ParseError::EndsBeforeStart);
     }
 
-    Some(Command::RepetitiveEvent(RepetitiveEventImpl {
-        event_start_time: time.unwrap(),
-        event_wait_time: dt.unwrap(),
+    Ok(Command::RepetitiveEvent(RepetitiveEventImpl {
+        event_start_time: start,
+        event_rule: rule,
         event_text: String::from(text),
+        event_until: until,
+        event_count: count,
+        event_timezone: zone,
+        event_leads: Vec::new(),
     }))
 }
 
-#[rustfmt::skip]
-fn get_duration_from_capture(cap: &Captures) -> Option<chrono::Duration>{
-    let day:    i64 = cap.name("d_day").map_or    (0, |c| c.as_str().parse().unwrap() );
-    let hour:   i64 = cap.name("d_hour").map_or   (0, |c| c.as_str().parse().unwrap() );
-    let minute: i64 = cap.name("d_minute").map_or (0, |c| c.as_str().parse().unwrap() );
-    let second: i64 = cap.name("d_second").map_or (0, |c| c.as_str().parse().unwrap() );
+/// End of the given local day, the year defaults to the nearest one.
+fn get_series_until(
+    cap: &Captures,
+    now: DateTime<Utc>,
+    tz: &Tz,
+    date_order: DateOrder,
+) -> Result<DateTime<Utc>, ParseError> {
+    let today = now.with_timezone(tz).date_naive();
+    let (day, month) =
+        date_order.day_month(parse_number(&cap["e_day"])?, parse_number(&cap["e_month"])?);
+    let check = |year| check_date(year, month, day, date_order);
+    let date = match cap.name("e_year") {
+        Some(year) => check(parse_number(year.as_str())?)?,
+        None => {
+            let date = check(today.year())?;
+            if date < today {
+                check(today.year() + 1)?
+            } else {
+                date
+            }
+        }
+    };
+    let next_day = date.succ_opt().ok_or(ParseError::NoMatch)?;
+    Ok(local_to_utc(tz, next_day.and_time(NaiveTime::MIN)))
+}
+
+/// Rule at the start of `line` and the text after it. Intervals may be
+/// spaced like in `for`, `2h 30m`, `1 week`, other rules are a single word.
+fn take_recurrence(line: &str) -> Result<(Recurrence, &str), ParseError> {
+    if let Ok((duration, rest)) = parse_duration(line) {
+        let rule = line[..line.len() - rest.len()].trim();
+        let rule = duration_rule(duration).ok_or(ParseError::UnknownRule(rule.to_owned()))?;
+        return Ok((rule, rest.trim()));
+    }
+    let (word, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    Ok((parse_recurrence(word)?, rest.trim()))
+}
+
+fn parse_recurrence(rule: &str) -> Result<Recurrence, ParseError> {
+    let unknown = || ParseError::UnknownRule(rule.to_owned());
+    let rule = rule.to_lowercase();
+    if let Ok(days) = parse_weekday_set(&rule) {
+        return Ok(Recurrence::Weekdays(days));
+    }
+    static REG: LazyLock<Regex> = LazyLock::new(|| {
+        let last = words_regex(|pack| pack.last.to_vec());
+        let reg = format!(
+            r"^(?:(?P<r_last_day>{last})|(?P<r_nth>[1-5]|{last}-)(?P<r_weekday>{}))$",
+            words_regex(|pack| names(pack.weekdays))
+        );
+        Regex::new(&reg).unwrap()
+    });
+    if let Some(cap) = REG.captures(&rule) {
+        if cap.name("r_last_day").is_some() {
+            return Ok(Recurrence::LastDayOfMonth);
+        }
+        return Ok(Recurrence::WeekdayOfMonth {
+            nth: cap["r_nth"].parse().unwrap_or(-1),
+            weekday: parse_weekday(&cap["r_weekday"]).ok_or_else(unknown)?,
+        });
+    }
 
-    if day == 0 && hour == 0 && minute == 0 && second == 0 {
-        return None;
+    match parse_duration(&rule) {
+        Ok((duration, "")) => duration_rule(duration).ok_or_else(unknown),
+        _ => Err(unknown()),
+    }
+}
+
+/// Repeat period of the given length, months can't be mixed with fixed
+/// units, e.g. `1mo2d`.
+fn duration_rule(duration: DurationSpec) -> Option<Recurrence> {
+    if duration.seconds == 0 {
+        Some(Recurrence::Months(duration.months))
+    } else if duration.months == 0 {
+        Some(Recurrence::Interval(chrono::Duration::seconds(
+            duration.seconds,
+        )))
+    } else {
+        None
+    }
+}
+
+fn parse_weekday(name: &str) -> Option<Weekday> {
+    find_word(name, |pack| pack.weekdays)
+}
+
+/// Comma separated weekday names or group names (`weekday`, `выходные`),
+/// sorted from Monday without duplicates.
+fn parse_weekday_set(names: &str) -> Result<Vec<Weekday>, ParseError> {
+    let mut days = Vec::new();
+    for name in names.split(',').map(str::trim) {
+        if let Some(group) = find_word(name, |pack| pack.weekday_groups) {
+            days.extend_from_slice(group);
+        } else {
+            days.push(parse_weekday(name).ok_or(ParseError::UnknownWeekday(name.to_owned()))?);
+        }
+    }
+    days.sort_by_key(|d| d.num_days_from_monday());
+    days.dedup();
+    Ok(days)
+}
+
+fn parse_number<T: std::str::FromStr>(digits: &str) -> Result<T, ParseError> {
+    digits
+        .parse()
+        .map_err(|_| ParseError::InvalidNumber(digits.to_owned()))
+}
+
+/// Event text after the time spec.
+fn get_text_from_capture<'a>(cap: &Captures<'a>) -> Result<&'a str, ParseError> {
+    match cap.name("main_text").map(|c| c.as_str().trim()) {
+        Some(text) if !text.is_empty() => Ok(text),
+        _ => Err(ParseError::MissingText),
+    }
+}
+
+/// Event text after the time spec, the zone written before it
+/// (`at 10 Europe/London call`) overrides the user's one.
+fn get_zone_and_text_from_capture<'a>(
+    cap: &Captures<'a>,
+    now: DateTime<Utc>,
+) -> Result<(Option<Tz>, &'a str), ParseError> {
+    let text = get_text_from_capture(cap)?;
+    let (word, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    match parse_zone_suffix(word, now) {
+        Some(_) if rest.trim().is_empty() => Err(ParseError::MissingText),
+        Some(zone) => Ok((Some(zone), rest.trim())),
+        None => Ok((None, text)),
+    }
+}
+
+/// Moves lead alerts from the start of the text to the event, so
+/// `at 14 -1h -15m standup` is `standup` with alerts an hour and 15 minutes
+/// before. Alerts are kept from the earliest one, duplicates are dropped.
+fn take_leads(mut command: Command) -> Result<Command, ParseError> {
+    let (text, leads) = match &mut command {
+        Command::OneTimeEvent(ev) => (&mut ev.event_text, &mut ev.event_leads),
+        Command::RepetitiveEvent(ev) => (&mut ev.event_text, &mut ev.event_leads),
+    };
+    static REG: LazyLock<Regex> = LazyLock::new(|| Regex::new(LEAD_REGEX).unwrap());
+    let mut rest = text.as_str();
+    loop {
+        let (word, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        if !REG.is_match(word) {
+            break;
+        }
+        let (duration, _) = match parse_duration(&word[1..]) {
+            Ok(duration) => duration,
+            // `-2fa` is the text
+            Err(ParseError::NoMatch) => break,
+            Err(e) => return Err(e),
+        };
+        // calendar months have no fixed length before a given time
+        if duration.months > 0 {
+            return Err(ParseError::InvalidLead(word.to_owned()));
+        }
+        leads.push(chrono::Duration::seconds(duration.seconds));
+        rest = tail.trim_start();
+    }
+    if leads.is_empty() {
+        return Ok(command);
+    }
+    if rest.is_empty() {
+        return Err(ParseError::MissingText);
+    }
+    *text = rest.to_owned();
+    leads.sort_by(|a, b| b.cmp(a));
+    leads.dedup();
+    Ok(command)
+}
+
+/// Length of a one-shot delay or a repeat period. Months (and years) are
+/// calendar ones, the rest is a fixed number of seconds.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct DurationSpec {
+    months: u32,
+    seconds: i64,
+}
+
+impl DurationSpec {
+    fn after(&self, now: DateTime<Utc>, tz: &Tz) -> DateTime<Utc> {
+        let mut time = now;
+        if self.months > 0 {
+            let local = now.with_timezone(tz).naive_local() + chrono::Months::new(self.months);
+            time = local_to_utc(tz, local);
+        }
+        time + chrono::Duration::seconds(self.seconds)
+    }
+}
+
+/// Duration at the start of `line` as number-unit pairs: `1d2h`, `2h 30m`,
+/// `1 day 3 hours`, `2mo`. Returns the rest of the line, parsing stops at the
+/// first word that is not a unit, so in `10m 2 apples` the text is `2 apples`.
+fn parse_duration(line: &str) -> Result<(DurationSpec, &str), ParseError> {
+    static REG: LazyLock<Regex> = LazyLock::new(|| Regex::new(DURATION_PART_REGEX).unwrap());
+    let mut duration = DurationSpec::default();
+    let mut found = false;
+    let mut rest = line;
+    while let Some(cap) = REG.captures(rest) {
+        let unit = cap["unit"].to_lowercase();
+        let spaced = !cap["space"].is_empty();
+        let unit_word = find_word(&unit, |pack| pack.units)
+            .filter(|_| !spaced || is_word(&unit, |pack| pack.spaced_units));
+        let Some((months, seconds)) = unit_word else {
+            // `1h5x`, `5hrz`: a misspelled unit, `5 apples`, `2fa`, `3rd`: just text
+            if !spaced && (found || is_unit_typo(&unit)) {
+                return Err(ParseError::UnknownUnit(cap["unit"].to_owned()));
+            }
+            break;
+        };
+        let too_big = || ParseError::InvalidNumber(cap[0].trim().to_owned());
+        let number: u32 = cap["number"].parse().map_err(|_| too_big())?;
+        duration.months = months
+            .checked_mul(number)
+            .and_then(|m| m.checked_add(duration.months))
+            .ok_or_else(too_big)?;
+        duration.seconds = seconds
+            .checked_mul(number as i64)
+            .and_then(|s| s.checked_add(duration.seconds))
+            .ok_or_else(too_big)?;
+        // anything longer than a thousand years is a typo
+        if duration.months > 12 * 1000 || duration.seconds > 1000 * 365 * SECONDS_IN_DAY {
+            return Err(too_big());
+        }
+        found = true;
+        rest = &rest[cap.get(0).unwrap().end()..];
+    }
+
+    if !found || !(rest.is_empty() || rest.starts_with(char::is_whitespace)) {
+        return Err(ParseError::NoMatch);
+    }
+    if duration == DurationSpec::default() {
+        return Err(ParseError::ZeroDuration);
     }
+    Ok((duration, rest))
+}
+
+/// One letter off a unit word, one-letter units are left out since `3rd`
+/// or `1st` would be one letter off `d` or `s`.
+fn is_unit_typo(unit: &str) -> bool {
+    LANGUAGE_PACKS
+        .iter()
+        .flat_map(|pack| pack.units)
+        .any(|(name, _)| name.chars().count() > 2 && edit_distance(unit, name) <= 1)
+}
 
-    Some(chrono::Duration::seconds(day * (60*60*24) 
-                                 + hour * (60*60) 
-                                 + minute * 60 
-                                 + second))
+/// Levenshtein distance in chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
 }
 
 #[rustfmt::skip]
-fn get_datetime_from_capture(cap: &Captures, now: DateTime<Utc>, tz: i32) -> Option<DateTime<Utc>>{
-    let dt = chrono::Duration::seconds((tz as i64) * 60 * 60);
-    let now = now - dt;
+fn get_datetime_from_capture(cap: &Captures, now: DateTime<Utc>, tz: &Tz, order: DateOrder) -> Result<DateTime<Utc>, ParseError>{
+    let now = now.with_timezone(tz);
+
+    // a single number is always the day, `10 at 9`
+    let first   = cap.name("m_day").map_or(Ok(now.day()),       |c| parse_number(c.as_str()))?;
+    let (day, month) = match (cap.name("m_month_name"), cap.name("m_month")) {
+        (Some(c), _) => (first, find_word(&c.as_str().to_lowercase(), |pack| pack.months).ok_or(ParseError::NoMatch)?),
+        (_, Some(c)) => order.day_month(first, parse_number(c.as_str())?),
+        (None, None) => (first, now.month()),
+    };
+    let year    = cap.name("m_year").map_or(Ok(now.year()),     |c| parse_number(c.as_str()))?;
+
+    let date = check_date(year, month, day, order)?;
+    let time = get_time_from_capture(cap)?;
+    Ok(local_to_utc(tz, date.and_time(time)))
+}
+
+/// `order` is kept in errors to show hints the way the user writes dates.
+fn check_date(year: i32, month: u32, day: u32, order: DateOrder) -> Result<NaiveDate, ParseError> {
+    if !(1..=12).contains(&month) {
+        return Err(ParseError::InvalidMonth { day, month, order });
+    }
+    NaiveDate::from_ymd_opt(year, month, day).ok_or(ParseError::InvalidDay {
+        day,
+        month,
+        year,
+        order,
+    })
+}
+
+fn get_time_from_capture(cap: &Captures) -> Result<NaiveTime, ParseError> {
+    let minute = cap
+        .name("m_minute")
+        .map_or(Ok(0), |c| parse_number(c.as_str()))?;
+    let hour: u32 = parse_number(&cap["m_hour"])?;
 
-    let day     = cap.name("m_day").map_or(now.day(),       |c| c.as_str().parse().unwrap());
-    let month   = cap.name("m_month").map_or(now.month(),   |c| c.as_str().parse().unwrap());
-    let year    = cap.name("m_year").map_or(now.year(),     |c| c.as_str().parse().unwrap());
-    let minute  = cap.name("m_minute").map_or(0,            |c| c.as_str().parse().unwrap());
-    
-    let hour    = cap.name("m_hour").unwrap().as_str().parse().unwrap();
-    
-    use chrono::offset::LocalResult::Single;
-    if let Single(date) = Utc.with_ymd_and_hms(year, month, day, hour, minute, 0) {
-        return Some(date + dt);
+    // 12 am is midnight, 12 pm is noon
+    let am_pm = cap.name("m_ampm").map(|c| c.as_str().to_lowercase());
+    let hour = match am_pm.as_deref() {
+        Some(_) if !(1..=12).contains(&hour) => {
+            return Err(ParseError::HourOutOfRange { hour, am_pm: true });
+        }
+        Some(word) if is_word(word, |pack| pack.am) => hour % 12,
+        Some(_) => hour % 12 + 12,
+        None if hour > 23 => return Err(ParseError::HourOutOfRange { hour, am_pm: false }),
+        None => hour,
+    };
+    if minute > 59 {
+        return Err(ParseError::MinuteOutOfRange(minute));
     }
-    None
+
+    Ok(NaiveTime::from_hms_opt(hour, minute, 0).unwrap())
 }
 
 //-------- TESTS ---------------------------------------------------------------
@@ -157,6 +1233,171 @@ fn get_datetime_from_capture(cap: &Captures, now: DateTime<Utc>, tz: i32) -> Opt
 mod tests {
     use super::*;
     use crate::command::Command::*;
+    use crate::text_data::Lang;
+    use chrono_tz::{Asia, Etc, Europe};
+
+    const MSK: Tz = Etc::GMTMinus3;
+    const UTC: Tz = Etc::UTC;
+
+    // parsers with the default date order, `parse_date_order` checks the other one
+    fn parse_command(
+        command_line: String,
+        now: DateTime<Utc>,
+        user_timezone: Tz,
+    ) -> Result<Command, ParseError> {
+        super::parse_command(command_line, now, user_timezone, DateOrder::default())
+    }
+
+    fn try_parse_at(line: &str, now: DateTime<Utc>, tz: &Tz) -> Result<Command, ParseError> {
+        super::try_parse_at(line, now, tz, DateOrder::default())
+    }
+
+    fn try_parse_iso(line: &str, tz: &Tz) -> Result<Command, ParseError> {
+        super::try_parse_iso(line, tz, DateOrder::default())
+    }
+
+    fn try_parse_rep(line: &str, now: DateTime<Utc>, tz: &Tz) -> Result<Command, ParseError> {
+        super::try_parse_rep(line, now, tz, DateOrder::default())
+    }
+
+    #[test]
+    fn zone_override() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 9, 0, 0).unwrap();
+        let utc = |m, d, h, min| Utc.with_ymd_and_hms(2026, m, d, h, min, 0).unwrap();
+        let parse = |line: &str| parse_command(line.to_owned(), now, MSK);
+        let expect = [
+            (
+                "at 10 UTC call",
+                utc(10, 18, 10, 0),
+                "call",
+                Some(chrono_tz::UTC),
+            ),
+            (
+                "15-11 at 9 Europe/London flight check-in",
+                utc(11, 15, 9, 0),
+                "flight check-in",
+                Some(Europe::London),
+            ),
+            (
+                "tomorrow at 9 PST call",
+                utc(10, 19, 17, 0),
+                "call",
+                Some(chrono_tz::Etc::GMTPlus8),
+            ),
+            ("at 10 call UTC", utc(10, 18, 7, 0), "call UTC", None),
+            ("at 10 utc call", utc(10, 18, 7, 0), "utc call", None),
+        ];
+        for (line, time, text, zone) in expect {
+            match parse(line) {
+                Ok(OneTimeEvent(res)) => {
+                    assert_eq!(res.event_time, time, "{line}");
+                    assert_eq!(res.event_text, text, "{line}");
+                    assert_eq!(res.event_timezone, zone, "{line}");
+                }
+                res => panic!("Unexpected result for {line}: {res:?}"),
+            }
+        }
+
+        match parse("at 9 +05:30 standup") {
+            Ok(OneTimeEvent(res)) => assert_eq!(res.event_time, utc(10, 18, 3, 30)),
+            res => panic!("Unexpected result: {res:?}"),
+        }
+        assert_eq!(parse("at 10 UTC"), Err(ParseError::MissingText));
+
+        // the rule is followed in the given zone, 9.00 Berlin time across DST
+        match parse("rep 9 Europe/Berlin 1d until 30-10 tea") {
+            Ok(RepetitiveEvent(res)) => {
+                assert_eq!(res.event_start_time, utc(10, 18, 7, 0));
+                assert_eq!(res.event_text, "tea");
+                assert_eq!(res.event_timezone, Some(Europe::Berlin));
+                assert_eq!(res.event_until, Some(utc(10, 30, 23, 0)));
+                let next = res.next_occurrences(&Europe::Berlin, now, 20);
+                assert_eq!(next.len(), 12);
+                assert_eq!(next.first(), Some(&utc(10, 19, 7, 0)));
+                assert_eq!(next.last(), Some(&utc(10, 30, 8, 0)));
+            }
+            res => panic!("Unexpected result: {res:?}"),
+        }
+        assert_eq!(
+            parse("rep 9 UTC tea time"),
+            Err(ParseError::UnknownRule("tea".to_owned()))
+        );
+    }
+
+    #[test]
+    fn lead_alerts() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 9, 0, 0).unwrap();
+        let parse = |line: &str| parse_command(line.to_owned(), now, MSK);
+        let minutes = |list: &[i64]| -> Vec<chrono::Duration> {
+            list.iter().map(|m| chrono::Duration::minutes(*m)).collect()
+        };
+
+        match parse("at 14 -15m -1h -15m standup") {
+            Ok(OneTimeEvent(res)) => {
+                assert_eq!(res.event_text, "standup");
+                assert_eq!(res.event_leads, minutes(&[60, 15]));
+            }
+            res => panic!("Unexpected result: {res:?}"),
+        }
+        match parse("at 14 UTC -1h30m call") {
+            Ok(OneTimeEvent(res)) => {
+                assert_eq!(res.event_text, "call");
+                assert_eq!(res.event_timezone, Some(chrono_tz::UTC));
+                assert_eq!(res.event_leads, minutes(&[90]));
+            }
+            res => panic!("Unexpected result: {res:?}"),
+        }
+        match parse("rep 9 1d x5 -10m standup") {
+            Ok(RepetitiveEvent(res)) => {
+                assert_eq!(res.event_text, "standup");
+                assert_eq!(res.event_count, Some(5));
+                assert_eq!(res.event_leads, minutes(&[10]));
+            }
+            res => panic!("Unexpected result: {res:?}"),
+        }
+        // only whole words at the start of the text are alerts
+        match parse("at 14 -5 degrees outside -15m") {
+            Ok(OneTimeEvent(res)) => {
+                assert_eq!(res.event_text, "-5 degrees outside -15m");
+                assert!(res.event_leads.is_empty());
+            }
+            res => panic!("Unexpected result: {res:?}"),
+        }
+
+        assert_eq!(parse("at 14 -15m"), Err(ParseError::MissingText));
+        assert_eq!(
+            parse("at 14 -1mo rent"),
+            Err(ParseError::InvalidLead("-1mo".to_owned()))
+        );
+        assert_eq!(
+            parse("at 14 -15hrz call"),
+            Err(ParseError::UnknownUnit("hrz".to_owned()))
+        );
+    }
+
+    #[test]
+    fn next_occurrences_respect_series_end() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 9, 0, 0).unwrap();
+        let day = |d| Utc.with_ymd_and_hms(2026, 10, d, 7, 0, 0).unwrap();
+        let mut ev = RepetitiveEventImpl {
+            event_start_time: day(18),
+            event_rule: Recurrence::Interval(chrono::Duration::days(1)),
+            event_text: "a".to_owned(),
+            event_until: None,
+            event_count: None,
+            event_timezone: None,
+            event_leads: Vec::new(),
+        };
+        assert_eq!(
+            ev.next_occurrences(&MSK, now, 3),
+            vec![day(19), day(20), day(21)]
+        );
+        ev.event_count = Some(2);
+        assert_eq!(ev.next_occurrences(&MSK, now, 5), vec![day(19), day(20)]);
+        ev.event_count = None;
+        ev.event_until = Some(day(21));
+        assert_eq!(ev.next_occurrences(&MSK, now, 5), vec![day(19), day(20)]);
+    }
 
     #[test]
     fn parse_for_general() {
@@ -168,8 +1409,8 @@ mod tests {
             + chrono::Duration::minutes(3)
             + chrono::Duration::seconds(4);
         let now = Utc::now();
-        let result = try_parse_for(&command_text, now);
-        assert!(result.is_some());
+        let result = try_parse_for(&command_text, now, &MSK);
+        assert!(result.is_ok());
         match result.unwrap() {
             OneTimeEvent(res) => {
                 assert_eq!(res.event_text, text);
@@ -186,8 +1427,8 @@ mod tests {
         let command_text = command + " " + text;
         let dt = chrono::Duration::hours(2) + chrono::Duration::minutes(30);
         let now = Utc::now();
-        let result = try_parse_for(&command_text, now);
-        assert!(result.is_some());
+        let result = try_parse_for(&command_text, now, &MSK);
+        assert!(result.is_ok());
         match result.unwrap() {
             OneTimeEvent(res) => {
                 assert_eq!(res.event_text, text);
@@ -203,8 +1444,8 @@ mod tests {
         let command = String::from("1d-2h3m4s");
         let text = "some text";
         let command_text = command + " " + text;
-        let result = try_parse_for(&command_text, Utc::now());
-        assert!(result.is_none());
+        let result = try_parse_for(&command_text, Utc::now(), &MSK);
+        assert!(result.is_err());
     }
 
     #[test]
@@ -213,8 +1454,98 @@ mod tests {
         let command = String::from("1d2j3m4s");
         let text = "some text";
         let command_text = command + " " + text;
-        let result = try_parse_for(&command_text, Utc::now());
-        assert!(result.is_none());
+        let result = try_parse_for(&command_text, Utc::now(), &MSK);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parse_for_units() {
+        // 31 Jan, 13:00 in Moscow
+        let now = Utc.with_ymd_and_hms(2026, 1, 31, 10, 0, 0).unwrap();
+        let day = chrono::Duration::days(1);
+        let hour = chrono::Duration::hours(1);
+        let minute = chrono::Duration::minutes(1);
+        let expect = [
+            ("1w a", now + day * 7),
+            ("2 weeks a", now + day * 14),
+            ("2н a", now + day * 14),
+            ("2h 30m a", now + hour * 2 + minute * 30),
+            ("1 day 3 hours a", now + day + hour * 3),
+            ("1 час 30 минут a", now + hour + minute * 30),
+            (
+                "1mo a",
+                Utc.with_ymd_and_hms(2026, 2, 28, 10, 0, 0).unwrap(),
+            ),
+            (
+                "1 месяц 2d a",
+                Utc.with_ymd_and_hms(2026, 3, 2, 10, 0, 0).unwrap(),
+            ),
+            ("1y a", Utc.with_ymd_and_hms(2027, 1, 31, 10, 0, 0).unwrap()),
+        ];
+        for (command, time) in expect {
+            match try_parse_for(command, now, &MSK) {
+                Ok(OneTimeEvent(res)) => {
+                    assert_eq!(res.event_time, time, "{command}");
+                    assert_eq!(res.event_text, "a", "{command}");
+                }
+                _ => panic!("Wrong command type for {command}"),
+            };
+        }
+        match try_parse_for("10m 2 apples", now, &MSK) {
+            Ok(OneTimeEvent(res)) => {
+                assert_eq!(res.event_time, now + minute * 10);
+                assert_eq!(res.event_text, "2 apples");
+            }
+            _ => panic!("Wrong command type"),
+        };
+        // short units only go right after the number
+        for command in ["5 с Машей", "2 min read", "5 m a", "10 с собой"] {
+            assert_eq!(
+                parse_command(command.to_owned(), now, MSK),
+                Err(ParseError::NoMatch),
+                "{command}"
+            );
+        }
+
+        let rules = [
+            ("rep 9 1w a", Ok(Recurrence::Interval(day * 7))),
+            ("rep 9 2mo a", Ok(Recurrence::Months(2))),
+            (
+                "rep 9 1h30m a",
+                Ok(Recurrence::Interval(hour + minute * 30)),
+            ),
+            (
+                "rep 9 1mo2d a",
+                Err(ParseError::UnknownRule("1mo2d".to_owned())),
+            ),
+            // spaced forms like in `for`
+            (
+                "rep 9 2h 30m a",
+                Ok(Recurrence::Interval(hour * 2 + minute * 30)),
+            ),
+            ("rep 9 1 week a", Ok(Recurrence::Interval(day * 7))),
+            ("rep 9 2 месяца a", Ok(Recurrence::Months(2))),
+            (
+                "rep 9 UTC 1 day 12 hours a",
+                Ok(Recurrence::Interval(day + hour * 12)),
+            ),
+            (
+                "rep 01-02 9 1 month 2 days a",
+                Err(ParseError::UnknownRule("1 month 2 days".to_owned())),
+            ),
+            ("rep 9 1 week", Err(ParseError::MissingText)),
+        ];
+        for (command, rule) in rules {
+            let result = try_parse_rep(command, now, &MSK).map(|res| match res {
+                RepetitiveEvent(res) => res.event_rule,
+                _ => panic!("Wrong command type for {command}"),
+            });
+            assert_eq!(result, rule, "{command}");
+        }
+        match try_parse_rep("rep 9 1 week 2 apples", now, &MSK) {
+            Ok(RepetitiveEvent(res)) => assert_eq!(res.event_text, "2 apples"),
+            _ => panic!("Wrong command type"),
+        };
     }
 
     #[test]
@@ -227,8 +1558,8 @@ mod tests {
             let t = Utc
                 .with_ymd_and_hms(now.year(), 10, 24, 18 - 3, 30, 0)
                 .unwrap();
-            let result = try_parse_at(&command_text, now, -3);
-            assert!(result.is_some());
+            let result = try_parse_at(&command_text, now, &MSK);
+            assert!(result.is_ok());
             match result.unwrap() {
                 OneTimeEvent(res) => {
                     assert_eq!(res.event_text, text);
@@ -247,8 +1578,8 @@ mod tests {
                 .with_ymd_and_hms(now.year(), now.month(), 24, 18 - 3, 30, 0)
                 .unwrap();
 
-            let result = try_parse_at(&command_text, now, -3);
-            assert!(result.is_some());
+            let result = try_parse_at(&command_text, now, &MSK);
+            assert!(result.is_ok());
             match result.unwrap() {
                 OneTimeEvent(res) => {
                     assert_eq!(res.event_text, text);
@@ -265,8 +1596,8 @@ mod tests {
                 .with_ymd_and_hms(now.year(), now.month(), now.day(), 18, 30, 0)
                 .unwrap();
 
-            let result = try_parse_at(&command_text, now, 0);
-            assert!(result.is_some());
+            let result = try_parse_at(&command_text, now, &UTC);
+            assert!(result.is_ok());
             match result.unwrap() {
                 OneTimeEvent(res) => {
                     assert_eq!(res.event_text, text);
@@ -283,8 +1614,8 @@ mod tests {
                 .with_ymd_and_hms(now.year(), now.month(), now.day(), 18, 0, 0)
                 .unwrap();
 
-            let result = try_parse_at(&command_text, now, 0);
-            assert!(result.is_some());
+            let result = try_parse_at(&command_text, now, &UTC);
+            assert!(result.is_ok());
             match result.unwrap() {
                 OneTimeEvent(res) => {
                     assert_eq!(res.event_text, text);
@@ -295,6 +1626,64 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_at_12_hour_tests() {
+        let now = Utc.with_ymd_and_hms(2024, 10, 24, 6, 0, 0).unwrap();
+        let msk = |h: u32, min| {
+            Utc.with_ymd_and_hms(2024, 10, 24, h, min, 0).unwrap() - chrono::Duration::hours(3)
+        };
+        let expect = [
+            ("at 7pm some text", msk(19, 0)),
+            ("at 7:30 am some text", msk(7, 30)),
+            ("24-10 at 7.30PM some text", msk(19, 30)),
+            ("at 12am some text", msk(0, 0)),
+            ("at 12 pm some text", msk(12, 0)),
+        ];
+        for (command, time) in expect {
+            match parse_command(command.to_owned(), now, MSK) {
+                Ok(OneTimeEvent(res)) => {
+                    assert_eq!(res.event_time, time, "{command}");
+                    assert_eq!(res.event_text, "some text");
+                }
+                _ => panic!("Wrong command type for {command}"),
+            };
+        }
+        // "am" starting the text is not a suffix
+        match parse_command("at 7 amazing party".to_owned(), now, MSK) {
+            Ok(OneTimeEvent(res)) => {
+                assert_eq!(res.event_time, msk(7, 0));
+                assert_eq!(res.event_text, "amazing party");
+            }
+            _ => panic!("Wrong command type"),
+        };
+        assert!(parse_command("at 13pm some text".to_owned(), now, MSK).is_err());
+        assert!(parse_command("at 0 am some text".to_owned(), now, MSK).is_err());
+    }
+
+    #[test]
+    fn parse_iso_tests() {
+        let t = Utc.with_ymd_and_hms(2026, 11, 3, 11, 0, 0).unwrap();
+        let expect = [
+            ("2026-11-03T14:00 some text", t),
+            ("2026-11-03T14:00:00 some text", t),
+            ("2026-11-03T11:00Z some text", t),
+            ("2026-11-03T13:00+02:00 some text", t),
+            ("2026-11-03T06:30-0430 some text", t),
+            ("2026-11-03T16:00+05 some text", t),
+        ];
+        for (command, time) in expect {
+            match try_parse_iso(command, &MSK) {
+                Ok(OneTimeEvent(res)) => {
+                    assert_eq!(res.event_time, time, "{command}");
+                    assert_eq!(res.event_text, "some text");
+                }
+                _ => panic!("Wrong command type for {command}"),
+            };
+        }
+        assert!(try_parse_iso("2026-13-03T14:00 some text", &MSK).is_err());
+        assert!(try_parse_iso("2026-11-03T24:30 some text", &MSK).is_err());
+    }
+
     #[test]
     fn parse_wrong_at_tests() {
         {
@@ -302,32 +1691,32 @@ mod tests {
             let text = "some text";
             let command_text = command + " " + text;
 
-            let result = try_parse_at(&command_text, Utc::now(), -3);
-            assert!(result.is_none());
+            let result = try_parse_at(&command_text, Utc::now(), &MSK);
+            assert!(result.is_err());
         }
         {
             let command = String::from("30-20 at 18");
             let text = "some text";
             let command_text = command + " " + text;
 
-            let result = try_parse_at(&command_text, Utc::now(), -3);
-            assert!(result.is_none());
+            let result = try_parse_at(&command_text, Utc::now(), &MSK);
+            assert!(result.is_err());
         }
         {
             let command = String::from("at 25");
             let text = "some text";
             let command_text = command + " " + text;
 
-            let result = try_parse_at(&command_text, Utc::now(), -3);
-            assert!(result.is_none());
+            let result = try_parse_at(&command_text, Utc::now(), &MSK);
+            assert!(result.is_err());
         }
         {
             let command = String::from("at 23.60");
             let text = "some text";
             let command_text = command + " " + text;
 
-            let result = try_parse_at(&command_text, Utc::now(), -3);
-            assert!(result.is_none());
+            let result = try_parse_at(&command_text, Utc::now(), &MSK);
+            assert!(result.is_err());
         }
     }
 
@@ -341,18 +1730,404 @@ mod tests {
             .with_ymd_and_hms(now.year(), 10, 6, 10 - 3, 0, 0)
             .unwrap();
         let dt = chrono::Duration::minutes(5);
-        let result = try_parse_rep(&command_text, now, -3);
-        assert!(result.is_some());
+        let result = try_parse_rep(&command_text, now, &MSK);
+        assert!(result.is_ok());
         match result.unwrap() {
             RepetitiveEvent(res) => {
                 assert_eq!(res.event_start_time, t);
-                assert_eq!(res.event_wait_time, dt);
+                assert_eq!(res.event_rule, Recurrence::Interval(dt));
                 assert_eq!(res.event_text, text);
             }
             _ => panic!("Wrong command type"),
         };
     }
 
+    #[test]
+    fn parse_at_named_zones() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 20, 12, 0, 0).unwrap();
+        let expect = [
+            // half-hour offset
+            ("20-03 at 9.00", Asia::Kolkata, (2026, 3, 20, 3, 30)),
+            // before and after the DST switch
+            ("28-03 at 9.00", Europe::Berlin, (2026, 3, 28, 8, 0)),
+            ("30-03 at 9.00", Europe::Berlin, (2026, 3, 30, 7, 0)),
+            // 02:30 does not exist on the switch day, moves to 03:30 CEST
+            ("29-03 at 2.30", Europe::Berlin, (2026, 3, 29, 1, 30)),
+            // 02:30 happens twice on the switch back, take the first one
+            ("25-10 at 2.30", Europe::Berlin, (2026, 10, 25, 0, 30)),
+        ];
+        for (command, tz, (y, m, d, h, min)) in expect {
+            let command_text = format!("{command} some text");
+            let t = Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap();
+            match try_parse_at(&command_text, now, &tz) {
+                Ok(OneTimeEvent(res)) => assert_eq!(res.event_time, t, "{command} in {tz}"),
+                _ => panic!("Wrong command type"),
+            };
+        }
+    }
+
+    #[test]
+    fn parse_rep_calendar_rules() {
+        let now = Utc.with_ymd_and_hms(2026, 1, 5, 0, 0, 0).unwrap();
+        let expect = [
+            ("rep 01-02 10.00 1mo pay rent", Recurrence::Months(1)),
+            (
+                "rep 31-01 10.00 3мес квартальный отчет",
+                Recurrence::Months(3),
+            ),
+            ("rep 12-03 9 1y birthday", Recurrence::Months(12)),
+            ("rep 12-03 9 1г день рождения", Recurrence::Months(12)),
+            ("rep 10.00 last salary", Recurrence::LastDayOfMonth),
+            (
+                "rep 10.00 2tue meetup",
+                Recurrence::WeekdayOfMonth {
+                    nth: 2,
+                    weekday: Weekday::Tue,
+                },
+            ),
+            (
+                "rep 18 посл-пт пятничный созвон",
+                Recurrence::WeekdayOfMonth {
+                    nth: -1,
+                    weekday: Weekday::Fri,
+                },
+            ),
+        ];
+        for (command, rule) in expect {
+            match try_parse_rep(command, now, &UTC) {
+                Ok(RepetitiveEvent(res)) => assert_eq!(res.event_rule, rule, "{command}"),
+                _ => panic!("Wrong command type for {command}"),
+            };
+        }
+
+        for command in [
+            "rep 10.00 0mo text",
+            "rep 10.00 2xyz text",
+            "rep 10.00 6tue text",
+        ] {
+            assert!(try_parse_rep(command, now, &UTC).is_err(), "{command}");
+        }
+    }
+
+    #[test]
+    fn parse_weekday_rules() {
+        use Weekday::*;
+        // Monday
+        let now = Utc.with_ymd_and_hms(2026, 1, 5, 12, 0, 0).unwrap();
+        let t = Utc.with_ymd_and_hms(2026, 1, 5, 8, 30, 0).unwrap();
+        let expect = [
+            (
+                "every mon,wed,fri at 8.30 standup",
+                vec![Mon, Wed, Fri],
+                "standup",
+            ),
+            ("every Fri, mon at 8:30 gym", vec![Mon, Fri], "gym"),
+            (
+                "каждый пн,ср,пт в 8.30 зарядка",
+                vec![Mon, Wed, Fri],
+                "зарядка",
+            ),
+            ("каждую субботу в 8.30 уборка", vec![Sat], "уборка"),
+            (
+                "every weekday at 8.30 work",
+                vec![Mon, Tue, Wed, Thu, Fri],
+                "work",
+            ),
+            (
+                "every weekend,mon at 8.30 rest",
+                vec![Mon, Sat, Sun],
+                "rest",
+            ),
+        ];
+        for (command, days, text) in expect {
+            match parse_command(command.to_owned(), now, UTC) {
+                Ok(RepetitiveEvent(res)) => {
+                    assert_eq!(res.event_rule, Recurrence::Weekdays(days), "{command}");
+                    assert_eq!(res.event_start_time, t, "{command}");
+                    assert_eq!(res.event_text, text);
+                }
+                _ => panic!("Wrong command type for {command}"),
+            };
+        }
+
+        match try_parse_rep("rep 8.30 tue,thu gym", now, &UTC) {
+            Ok(RepetitiveEvent(res)) => {
+                assert_eq!(res.event_rule, Recurrence::Weekdays(vec![Tue, Thu]))
+            }
+            _ => panic!("Wrong command type"),
+        };
+        match try_parse_rep("rep 8.30 sat gym", now, &UTC) {
+            Ok(RepetitiveEvent(res)) => {
+                assert_eq!(res.event_rule, Recurrence::Weekdays(vec![Sat]))
+            }
+            _ => panic!("Wrong command type"),
+        };
+        assert!(parse_command("every mon,xyz at 8.30 text".to_owned(), now, UTC).is_err());
+    }
+
+    #[test]
+    fn parse_relative_days() {
+        // Sunday 18 Oct 2026, 12:00 in Moscow
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 9, 0, 0).unwrap();
+        let msk = |d, h: u32, min| Utc.with_ymd_and_hms(2026, 10, d, h - 3, min, 0).unwrap();
+        let expect = [
+            ("today at 18 a", msk(18, 18, 0)),
+            ("Tomorrow at 9 a", msk(19, 9, 0)),
+            ("завтра в 9.30 a", msk(19, 9, 30)),
+            ("day after tomorrow at 7 a", msk(20, 7, 0)),
+            ("послезавтра в 7 a", msk(20, 7, 0)),
+            ("friday at 18 a", msk(23, 18, 0)),
+            ("on Monday at 10 a", msk(19, 10, 0)),
+            ("в пятницу в 18 a", msk(23, 18, 0)),
+            ("во вторник в 8 a", msk(20, 8, 0)),
+            // later today, otherwise next week
+            ("sunday at 13 a", msk(18, 13, 0)),
+            ("в воскресенье в 11 a", msk(25, 11, 0)),
+        ];
+        for (command, time) in expect {
+            match parse_command(command.to_owned(), now, MSK) {
+                Ok(OneTimeEvent(res)) => {
+                    assert_eq!(res.event_time, time, "{command}");
+                    assert_eq!(res.event_text, "a");
+                }
+                _ => panic!("Wrong command type for {command}"),
+            };
+        }
+        assert!(parse_command("someday at 9 a".to_owned(), now, MSK).is_err());
+        assert!(parse_command("tomorrow at 25 a".to_owned(), now, MSK).is_err());
+    }
+
+    #[test]
+    fn parse_language_packs() {
+        // 18 Oct 2026, 12:00 in Moscow
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 9, 0, 0).unwrap();
+        let msk = |m, d, h: u32| Utc.with_ymd_and_hms(2026, m, d, h - 3, 0, 0).unwrap();
+        let expect = [
+            ("10 nov at 12 a", msk(11, 10, 12)),
+            ("10 November AT 12 a", msk(11, 10, 12)),
+            ("10 ноября в 12 a", msk(11, 10, 12)),
+            ("10 дек В 12 a", msk(12, 10, 12)),
+            ("в 7 вечера a", msk(10, 18, 19)),
+            ("в 11 утра a", msk(10, 18, 11)),
+        ];
+        for (command, time) in expect {
+            match parse_command(command.to_owned(), now, MSK) {
+                Ok(OneTimeEvent(res)) => {
+                    assert_eq!(res.event_time, time, "{command}");
+                    assert_eq!(res.event_text, "a");
+                }
+                _ => panic!("Wrong command type for {command}"),
+            };
+        }
+        for command in ["повтор 9 1д a", "Repeat 9 1d a", "REP 9 1d a"] {
+            match parse_command(command.to_owned(), now, MSK) {
+                Ok(RepetitiveEvent(res)) => {
+                    assert_eq!(res.event_start_time, msk(10, 18, 9), "{command}");
+                    assert_eq!(
+                        res.event_rule,
+                        Recurrence::Interval(chrono::Duration::days(1))
+                    );
+                }
+                _ => panic!("Wrong command type for {command}"),
+            };
+        }
+    }
+
+    #[test]
+    fn parse_series_end() {
+        // 18 Oct 2026, 12:00 in Moscow
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 9, 0, 0).unwrap();
+        match parse_command("rep 8.30 1d until 31-12 x10 gym".to_owned(), now, MSK) {
+            Ok(RepetitiveEvent(res)) => {
+                assert_eq!(
+                    res.event_until,
+                    Some(Utc.with_ymd_and_hms(2026, 12, 31, 21, 0, 0).unwrap())
+                );
+                assert_eq!(res.event_count, Some(10));
+                assert_eq!(res.event_text, "gym");
+            }
+            _ => panic!("Wrong command type"),
+        };
+        // past date means next year
+        match parse_command("every mon at 9 х3 до 01-02 отчет".to_owned(), now, MSK) {
+            Ok(RepetitiveEvent(res)) => {
+                assert_eq!(
+                    res.event_until,
+                    Some(Utc.with_ymd_and_hms(2027, 2, 1, 21, 0, 0).unwrap())
+                );
+                assert_eq!(res.event_count, Some(3));
+                assert_eq!(res.event_text, "отчет");
+            }
+            _ => panic!("Wrong command type"),
+        };
+        match parse_command("rep 8.30 1d gym".to_owned(), now, MSK) {
+            Ok(RepetitiveEvent(res)) => {
+                assert_eq!((res.event_until, res.event_count), (None, None));
+            }
+            _ => panic!("Wrong command type"),
+        };
+        // ends before the first occurrence
+        assert!(
+            parse_command("rep 20-10 8.30 1d until 19-10-2026 a".to_owned(), now, MSK).is_err()
+        );
+        assert!(parse_command("rep 8.30 1d x0 a".to_owned(), now, MSK).is_err());
+        assert!(parse_command("rep 8.30 1d until 32-12 a".to_owned(), now, MSK).is_err());
+    }
+
+    #[test]
+    fn parse_errors() {
+        use ParseError::*;
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 9, 0, 0).unwrap();
+        let expect = [
+            (
+                "10-13 at 25.00 meeting",
+                InvalidMonth {
+                    day: 10,
+                    month: 13,
+                    order: DateOrder::DayMonth,
+                },
+            ),
+            (
+                "31-02 at 9 a",
+                InvalidDay {
+                    day: 31,
+                    month: 2,
+                    year: 2026,
+                    order: DateOrder::DayMonth,
+                },
+            ),
+            (
+                "at 25 a",
+                HourOutOfRange {
+                    hour: 25,
+                    am_pm: false,
+                },
+            ),
+            ("tomorrow at 9.75 a", MinuteOutOfRange(75)),
+            (
+                "at 13pm a",
+                HourOutOfRange {
+                    hour: 13,
+                    am_pm: true,
+                },
+            ),
+            ("at 18.30", MissingText),
+            ("15m", MissingText),
+            ("5minuts call mom", UnknownUnit("minuts".to_owned())),
+            ("1h5q call mom", UnknownUnit("q".to_owned())),
+            ("0m tea", ZeroDuration),
+            (
+                "99999999999999d tea",
+                InvalidNumber("99999999999999d".to_owned()),
+            ),
+            ("rep 9 7z a", UnknownRule("7z".to_owned())),
+            ("every mon,xyz at 8.30 a", UnknownWeekday("xyz".to_owned())),
+            (r#"cron "0 9 * *" a"#, InvalidCron("0 9 * *".to_owned())),
+            ("rep 8.30 1d x0 a", ZeroCount),
+            ("rep 20-10 8.30 1d until 19-10-2026 a", EndsBeforeStart),
+            (
+                "2026-11-03T14:00+02:75 a",
+                InvalidOffset("+02:75".to_owned()),
+            ),
+            ("buy milk", NoMatch),
+            ("meet at 9 with John", NoMatch),
+            // a number with letters is not always a time
+            ("5x call mom", NoMatch),
+            ("2fa code is 1234", NoMatch),
+            ("1st place", NoMatch),
+            ("3rd floor", NoMatch),
+        ];
+        for (command, error) in expect {
+            assert_eq!(
+                parse_command(command.to_owned(), now, MSK),
+                Err(error),
+                "{command}"
+            );
+        }
+
+        let hint = |command: &str| {
+            parse_command(command.to_owned(), now, MSK)
+                .map_err(|e| Lang::En.parse_error(&e))
+                .unwrap_err()
+        };
+        assert!(hint("10-13 at 9 a").ends_with("Did you mean 13-10?"));
+        assert!(hint("31-02 at 9 a").ends_with("Did you mean 28-02?"));
+        assert!(hint("at 24.00 a").ends_with("Did you mean 0.00?"));
+    }
+
+    #[test]
+    fn parse_date_order() {
+        use DateOrder::*;
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 9, 0, 0).unwrap();
+        let msk = |m, d, h: u32| Utc.with_ymd_and_hms(2026, m, d, h - 3, 0, 0).unwrap();
+        let expect = [
+            ("03-11 at 9 a", DayMonth, msk(11, 3, 9)),
+            ("03-11 at 9 a", MonthDay, msk(3, 11, 9)),
+            ("12-31 at 9 a", MonthDay, msk(12, 31, 9)),
+            // single numbers and month names don't depend on the order
+            ("25 at 9 a", MonthDay, msk(10, 25, 9)),
+            ("10 nov at 9 a", MonthDay, msk(11, 10, 9)),
+        ];
+        for (command, order, time) in expect {
+            match super::parse_command(command.to_owned(), now, MSK, order) {
+                Ok(OneTimeEvent(res)) => assert_eq!(res.event_time, time, "{command}"),
+                _ => panic!("Wrong command type for {command}"),
+            };
+        }
+        match super::parse_command("rep 9 1d until 12-31 a".to_owned(), now, MSK, MonthDay) {
+            Ok(RepetitiveEvent(res)) => {
+                assert_eq!(
+                    res.event_until,
+                    Some(Utc.with_ymd_and_hms(2026, 12, 31, 21, 0, 0).unwrap())
+                );
+            }
+            _ => panic!("Wrong command type"),
+        };
+
+        let error =
+            super::parse_command("31-12 at 9 a".to_owned(), now, MSK, MonthDay).unwrap_err();
+        assert_eq!(
+            error,
+            ParseError::InvalidMonth {
+                day: 12,
+                month: 31,
+                order: MonthDay
+            }
+        );
+        let hint = Lang::En.parse_error(&error);
+        assert!(hint.contains("MM-DD"));
+        assert!(hint.ends_with("Did you mean 12-31?"));
+
+        // ISO dates are always year first, hints follow the user's order
+        assert_eq!(
+            super::parse_command("2026-13-03T14:00 a".to_owned(), now, MSK, MonthDay),
+            Err(ParseError::InvalidMonth {
+                day: 3,
+                month: 13,
+                order: MonthDay
+            })
+        );
+    }
+
+    #[test]
+    fn parse_cron_rules() {
+        // Monday, 15:00 in Moscow
+        let now = Utc.with_ymd_and_hms(2026, 1, 5, 12, 0, 0).unwrap();
+        match parse_command(r#"cron "0 9 * * 1-5" standup"#.to_owned(), now, MSK) {
+            Ok(RepetitiveEvent(res)) => {
+                assert_eq!(res.event_rule, Recurrence::cron("0 9 * * 1-5").unwrap());
+                assert_eq!(
+                    res.event_start_time,
+                    Utc.with_ymd_and_hms(2026, 1, 6, 6, 0, 0).unwrap()
+                );
+                assert_eq!(res.event_text, "standup");
+            }
+            _ => panic!("Wrong command type"),
+        };
+        assert!(parse_command(r#"cron "0 9 * *" text"#.to_owned(), now, MSK).is_err());
+        assert!(parse_command(r#"cron "0 9 * * 1-5""#.to_owned(), now, MSK).is_err());
+    }
+
     fn time_moment_eq(t1: DateTime<Utc>, t2: DateTime<Utc>) -> bool {
         t1.signed_duration_since(t2).num_milliseconds().abs() < 100
     }
@@ -1,11 +1,18 @@
-use crate::command::{Command, OneTimeEventImpl, RepetitiveEventImpl};
+use crate::command::{Command, DateOrder, OneTimeEventImpl, RepetitiveEventImpl};
+use crate::recurrence::Recurrence;
 use crate::sql_query as sql_q;
+use crate::text_data::Lang;
+use crate::timezone::{DEFAULT_TIMEZONE, local_to_utc};
 use anyhow::Result;
 use chrono::Utc;
 use chrono::prelude::*;
-use log::error;
+use chrono_tz::Tz;
+use log::{error, info, warn};
 use rusqlite::{Connection, params};
 
+// fired events nobody marked as done or snoozed are dropped after that
+const FIRED_EVENT_KEEP_DAYS: i64 = 7;
+
 pub struct DataBase {
     conn: Connection,
 }
@@ -22,13 +29,62 @@ pub struct UserInfo<'a> {
     pub chat_id: i64,
     pub first_name: &'a str,
     pub last_name: &'a str,
-    pub tz: i32,
+    pub tz: Tz,
+    pub lang: Lang,
+}
+
+/// How persistent reminders are sent again until marked as done.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NagSettings {
+    pub interval_minutes: u32,
+    pub max_repeats: u32,
+}
+
+impl Default for NagSettings {
+    fn default() -> Self {
+        NagSettings {
+            interval_minutes: 5,
+            max_repeats: 12,
+        }
+    }
+}
+
+impl NagSettings {
+    fn next(&self, time: DateTime<Utc>) -> i64 {
+        (time + chrono::Duration::minutes(self.interval_minutes as i64)).timestamp()
+    }
+}
+
+/// Local time window when reminders are held back, may span midnight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// Nearest end of the window after `time`.
+    fn end_after(&self, time: DateTime<Utc>, tz: &Tz) -> DateTime<Utc> {
+        next_local_time(self.end, time, tz)
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub struct RetrieveEventsResult {
+    /// Row of the fired event, kept for snoozing until done.
+    pub id: i64,
     pub command: Command,
     pub uid: i64,
+    /// How long before the event a lead alert is sent, `None` for the event.
+    pub lead: Option<chrono::Duration>,
 }
 
 impl DataBase {
@@ -39,6 +95,12 @@ impl DataBase {
             }
             DbMode::InMemory => Connection::open_in_memory().expect("Cannot open db in memory"),
         };
+        let fresh: bool = conn
+            .query_row(sql_q::USER_TABLE_EXISTS, params![], |row| {
+                row.get::<_, i64>(0)
+            })
+            .expect("Cannot check database schema")
+            == 0;
         conn.execute(sql_q::CREATE_USER_TABLE, params![])
             .expect("Cannot create user table");
         conn.execute(sql_q::CREATE_ACTIVE_EVENT_TABLE, params![])
@@ -47,6 +109,7 @@ impl DataBase {
             .expect("Cannot create scheduled_event table");
         conn.execute(sql_q::PRAGMA_FOREING_KEY, params![])
             .expect("Cannot apply pragma foreing key");
+        migrate(&conn, fresh);
         DataBase { conn }
     }
 
@@ -60,7 +123,8 @@ impl DataBase {
                     &info.first_name,
                     &info.last_name,
                     &info.chat_id,
-                    &info.tz
+                    &info.tz.name(),
+                    &info.lang.code()
                 ],
             )
             .map(|_| ())
@@ -68,9 +132,19 @@ impl DataBase {
     }
 
     pub fn put(&mut self, uid: i64, value: Command, now: DateTime<Utc>) -> bool {
+        self.put_event(uid, value, false, now)
+    }
+
+    /// Like `put`, but every fired occurrence is sent again until done.
+    pub fn put_persistent(&mut self, uid: i64, value: Command, now: DateTime<Utc>) -> bool {
+        self.put_event(uid, value, true, now)
+    }
+
+    fn put_event(&mut self, uid: i64, value: Command, nag: bool, now: DateTime<Utc>) -> bool {
+        let nag_left = nag.then(|| self.get_user_nag(uid).max_repeats);
         match value {
-            Command::OneTimeEvent(ev) => self.put_one_time_event(uid, -1, &ev),
-            Command::RepetitiveEvent(ev) => self.put_repetitive_event(uid, &ev, now),
+            Command::OneTimeEvent(ev) => self.put_one_time_event(uid, -1, &ev, nag_left, now),
+            Command::RepetitiveEvent(ev) => self.put_repetitive_event(uid, &ev, nag_left, now),
         }
     }
 
@@ -79,7 +153,12 @@ impl DataBase {
         time: DateTime<Utc>,
     ) -> Vec<RetrieveEventsResult> {
         let mut result: Vec<RetrieveEventsResult> = Vec::new();
-        let mut parent_vec: Vec<i64> = Vec::new();
+        // series to continue: parent, owner and the moment after which it goes on
+        let mut next_vec: Vec<(i64, i64, DateTime<Utc>)> = Vec::new();
+        let expired = time - chrono::Duration::days(FIRED_EVENT_KEEP_DAYS);
+        self.conn
+            .execute(sql_q::DELETE_FIRED_EVENTS_BEFORE, [&expired.timestamp()])
+            .expect("Cannot remove from active_event table");
         {
             let mut stmt = self
                 .conn
@@ -89,46 +168,281 @@ impl DataBase {
 
             while let Ok(Some(row)) = rows.next() {
                 let id: i64 = row.get(0).unwrap();
-                let command = Command::OneTimeEvent(OneTimeEventImpl {
+                let event_time = Utc
+                    .timestamp_opt(row.get(2).unwrap(), 0)
+                    .single()
+                    .expect("don't have time");
+                let uid = row.get(4).unwrap();
+                let parent_id: i64 = row.get(3).unwrap();
+                let snooze_count: Option<i64> = row.get(5).unwrap();
+                let lead_seconds: Option<i64> = row.get(7).unwrap();
+
+                if let Some(end) = self.quiet_end(uid, time) {
+                    // alerts are pointless after the night, so are frequent repeats
+                    let skip = lead_seconds.is_some()
+                        || (snooze_count.is_none()
+                            && parent_id != -1
+                            && self.repeats_within_hour(parent_id, event_time));
+                    if skip {
+                        self.conn
+                            .execute(sql_q::DELETE_ACTIVE_EVENT, [&id])
+                            .expect("Cannot remove from active_event table");
+                        if lead_seconds.is_none() {
+                            next_vec.push((parent_id, uid, end));
+                        }
+                    } else {
+                        self.conn
+                            .execute(sql_q::DEFER_ACTIVE_EVENT, [&id, &end.timestamp()])
+                            .expect("Cannot update active_event table");
+                    }
+                    continue;
+                }
+
+                if lead_seconds.is_some() {
+                    // alerts go out without buttons, nothing refers to them later
+                    self.conn
+                        .execute(sql_q::DELETE_ACTIVE_EVENT, [&id])
+                        .expect("Cannot remove from active_event table");
+                } else {
+                    let nag_left: Option<i64> = row.get(6).unwrap();
+                    let nag_time = nag_left.map(|_| self.get_user_nag(uid).next(time));
+                    self.conn
+                        .execute(
+                            sql_q::MARK_ACTIVE_EVENT_FIRED,
+                            params![&id, &time.timestamp(), &nag_time],
+                        )
+                        .expect("Cannot update active_event table");
+                }
+                // a snoozed occurrence was already followed by the next one,
+                // the next one of a series is scheduled by the event, not its alerts
+                if parent_id != -1 && snooze_count.is_none() && lead_seconds.is_none() {
+                    // the fired occurrence itself must not be scheduled again
+                    let after = time.max(event_time + chrono::Duration::seconds(1));
+                    next_vec.push((parent_id, uid, after));
+                }
+                result.push(RetrieveEventsResult {
+                    id,
+                    command: Command::OneTimeEvent(OneTimeEventImpl {
+                        event_text: row.get(1).unwrap(),
+                        event_time,
+                        event_timezone: None,
+                        event_leads: Vec::new(),
+                    }),
+                    uid,
+                    lead: lead_seconds.map(chrono::Duration::seconds),
+                });
+            }
+        }
+
+        for (parent_id, uid, after) in next_vec {
+            self.put_next_occurrence(parent_id, uid, after, time);
+        }
+
+        result.extend(self.extract_nags(time));
+        result
+    }
+
+    /// Schedules the occurrence of a series that follows `after`, or removes
+    /// the series when it is over.
+    fn put_next_occurrence(
+        &mut self,
+        parent_id: i64,
+        uid: i64,
+        after: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) {
+        let next = self
+            .conn
+            .query_row(sql_q::SELECT_REP_BY_ID, [&parent_id], |row| {
+                let until: Option<i64> = row.get(6).unwrap();
+                let count: Option<i64> = row.get(7).unwrap();
+                let nag: Option<bool> = row.get(8).unwrap();
+                Ok(
+                    rule_from_columns(row.get(3).unwrap(), row.get(5).unwrap()).map(|rule| {
+                        let event = create_nearest_active_event_from_repetitive(
+                            row.get(2).unwrap(),
+                            &rule,
+                            row.get(1).unwrap(),
+                            leads_from_column(row.get(9).unwrap()),
+                            &timezone_from_column(row.get(4).unwrap()),
+                            after,
+                        );
+                        (event, until, count, nag.unwrap_or(false))
+                    }),
+                )
+            });
+        let Some((event, until, count, nag)) = next.unwrap() else {
+            return;
+        };
+        let finished = count.is_some_and(|count| count <= 1)
+            || until.is_some_and(|until| event.event_time.timestamp() >= until);
+        if finished {
+            info!("Repetitive event {parent_id} is over");
+            self.conn
+                .execute(sql_q::DELETE_FROM_REP_BY_ID, [&parent_id])
+                .expect("Cannot remove from scheduled_event table");
+            return;
+        }
+        let nag_left = nag.then(|| self.get_user_nag(uid).max_repeats);
+        self.put_one_time_event(uid, parent_id, &event, nag_left, now);
+        if count.is_some() {
+            self.conn
+                .execute(sql_q::DECREMENT_REP_COUNT_BY_ID, [&parent_id])
+                .expect("Cannot update scheduled_event table");
+        }
+    }
+
+    /// Whether the series repeats sooner than an hour after `time`.
+    fn repeats_within_hour(&self, parent_id: i64, time: DateTime<Utc>) -> bool {
+        let next = self
+            .conn
+            .query_row(sql_q::SELECT_REP_BY_ID, [&parent_id], |row| {
+                let start: i64 = row.get(2)?;
+                let tz = timezone_from_column(row.get(4)?);
+                Ok(rule_from_columns(row.get(3)?, row.get(5)?).map(|rule| {
+                    let start = Utc
+                        .timestamp_opt(start, 0)
+                        .single()
+                        .expect("don't have time");
+                    rule.next_occurrence(start, &tz, time + chrono::Duration::seconds(1))
+                }))
+            })
+            .ok()
+            .flatten();
+        next.is_some_and(|next| next - time < chrono::Duration::hours(1))
+    }
+
+    /// End of the user's quiet hours when `time` falls into them.
+    fn quiet_end(&self, uid: i64, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let quiet = self.get_user_quiet(uid)?;
+        let tz = self.get_user_timezone(uid);
+        quiet
+            .contains(time.with_timezone(&tz).time())
+            .then(|| quiet.end_after(time, &tz))
+    }
+
+    /// Fired persistent events due to be sent again.
+    fn extract_nags(&mut self, time: DateTime<Utc>) -> Vec<RetrieveEventsResult> {
+        let mut result = Vec::new();
+        let mut stmt = self
+            .conn
+            .prepare(sql_q::SELECT_NAGS_LESS_BY_TIMESTAMP)
+            .expect("error in sql query");
+        let mut rows = (stmt.query([&time.timestamp()])).unwrap();
+        while let Ok(Some(row)) = rows.next() {
+            result.push(RetrieveEventsResult {
+                id: row.get(0).unwrap(),
+                command: Command::OneTimeEvent(OneTimeEventImpl {
                     event_text: row.get(1).unwrap(),
                     event_time: Utc
                         .timestamp_opt(row.get(2).unwrap(), 0)
                         .single()
                         .expect("don't have time"),
-                });
-                self.conn
-                    .execute(sql_q::DELETE_FROM_ACTIVE_EVENT_BY_ID, [&id])
-                    .expect("Cannot remove from one_time_event table");
-                let parent_id = row.get(3).unwrap();
-                let uid = row.get(4).unwrap();
-                result.push(RetrieveEventsResult { command, uid });
-                parent_vec.push(parent_id);
+                    event_timezone: None,
+                    event_leads: Vec::new(),
+                }),
+                uid: row.get(3).unwrap(),
+                lead: None,
+            });
+        }
+        drop(rows);
+        drop(stmt);
+
+        // held back till the end of quiet hours, the repeat is not used up
+        let mut held = Vec::new();
+        result.retain(|nag| match self.quiet_end(nag.uid, time) {
+            Some(end) => {
+                held.push((nag.id, end.timestamp()));
+                false
             }
+            None => true,
+        });
+        for (id, end) in held {
+            self.conn
+                .execute(sql_q::DEFER_NAG, [&id, &end])
+                .expect("Cannot update active_event table");
         }
+        for nag in &result {
+            let next = self.get_user_nag(nag.uid).next(time);
+            self.conn
+                .execute(sql_q::RESEND_NAG, [&nag.id, &next])
+                .expect("Cannot update active_event table");
+        }
+        result
+    }
 
-        for (parent_id, command) in parent_vec.iter().zip(result.iter()) {
-            if *parent_id != -1 {
-                let event = self
-                    .conn
-                    .query_row(sql_q::SELECT_REP_BY_ID, [&parent_id], |row| {
-                        Ok(create_nearest_active_event_from_repetitive(
-                            row.get(2).unwrap(),
-                            row.get(3).unwrap(),
-                            row.get(1).unwrap(),
-                            time,
-                        ))
-                    });
-                let event = event.unwrap();
-                self.put_one_time_event(command.uid, *parent_id, &event);
+    /// Makes a fired event persistent, it's sent again until done.
+    pub fn start_nag(&mut self, id: i64, uid: i64, now: DateTime<Utc>) -> bool {
+        let nag = self.get_user_nag(uid);
+        let res = self.conn.execute(
+            sql_q::START_NAG,
+            params![&id, &uid, &nag.max_repeats, &nag.next(now)],
+        );
+        match res {
+            Ok(updated) => updated == 1,
+            Err(e) => {
+                error!("Can't start nagging for event {id}. Reason: {e}");
+                false
             }
         }
+    }
 
-        result
+    /// Text and time of an event that fired and waits for the user.
+    pub fn get_fired_event(&self, id: i64, uid: i64) -> Option<OneTimeEventImpl> {
+        self.conn
+            .query_row(sql_q::SELECT_FIRED_EVENT, [&id, &uid], |row| {
+                Ok(OneTimeEventImpl {
+                    event_text: row.get(0)?,
+                    event_time: Utc
+                        .timestamp_opt(row.get(1)?, 0)
+                        .single()
+                        .expect("don't have time"),
+                    event_timezone: None,
+                    event_leads: Vec::new(),
+                })
+            })
+            .ok()
+    }
+
+    /// Schedules a fired event again, the row and its history stay the same.
+    pub fn snooze_event(&mut self, id: i64, uid: i64, time: DateTime<Utc>) -> bool {
+        let res = self.conn.execute(
+            sql_q::SNOOZE_FIRED_EVENT,
+            params![&id, &uid, &time.timestamp()],
+        );
+        // alerts made for the old time would come after the event
+        let res = res.and_then(|updated| match updated {
+            1 => self
+                .conn
+                .execute(sql_q::DELETE_PENDING_LEAD_ALERTS, [&id])
+                .map(|_| updated),
+            _ => Ok(updated),
+        });
+        match res {
+            Ok(updated) => updated == 1,
+            Err(e) => {
+                error!("Can't snooze event {id}. Reason: {e}");
+                false
+            }
+        }
+    }
+
+    pub fn delete_fired_event(&mut self, id: i64, uid: i64) -> bool {
+        let res = self
+            .conn
+            .execute(sql_q::DELETE_FIRED_EVENT, params![&id, &uid]);
+        match res {
+            Ok(deleted) => deleted == 1,
+            Err(e) => {
+                error!("Can't delete fired event {id}. Reason: {e}");
+                false
+            }
+        }
     }
 
     pub fn get_nearest_wakeup(&self) -> Option<DateTime<Utc>> {
         self.conn
-            .query_row(sql_q::MIN_TIMESTAMP_FROM_ACTIVE_EVENT, params![], |row| {
+            .query_row(sql_q::MIN_WAKEUP_TIMESTAMP, params![], |row| {
                 row.get(0).map(|expr| {
                     Utc.timestamp_opt(expr, 0)
                         .single()
@@ -138,7 +452,9 @@ impl DataBase {
             .ok()
     }
 
-    pub fn get_all_active_events(&self, uid: i64) -> Vec<Command> {
+    /// Active events with the rule of the repetitive event they come from
+    /// and the id of their row.
+    pub fn get_all_active_events(&self, uid: i64) -> Vec<(Command, Option<Recurrence>, i64)> {
         let mut result = Vec::new();
 
         let mut stmt = self
@@ -147,13 +463,25 @@ impl DataBase {
             .expect("error in sql connection prepare");
         let command_iter = stmt
             .query_map([&uid], |row| {
-                Ok(Command::OneTimeEvent(OneTimeEventImpl {
-                    event_text: row.get(1).unwrap(),
-                    event_time: Utc
-                        .timestamp_opt(row.get(2).unwrap(), 0)
-                        .single()
-                        .expect("don't have time"),
-                }))
+                let parent_id: i64 = row.get(5).unwrap();
+                let rule = if parent_id != -1 {
+                    rule_from_columns(row.get(3).unwrap(), row.get(4).unwrap())
+                } else {
+                    None
+                };
+                Ok((
+                    Command::OneTimeEvent(OneTimeEventImpl {
+                        event_text: row.get(1).unwrap(),
+                        event_time: Utc
+                            .timestamp_opt(row.get(2).unwrap(), 0)
+                            .single()
+                            .expect("don't have time"),
+                        event_timezone: None,
+                        event_leads: leads_from_column(row.get(6).unwrap()),
+                    }),
+                    rule,
+                    row.get(0).unwrap(),
+                ))
             })
             .expect("error in query map");
 
@@ -173,27 +501,28 @@ impl DataBase {
             .expect("error in sql connection prepare");
         let command_iter = stmt
             .query_map([&uid], |row| {
-                Ok((
-                    Command::RepetitiveEvent(RepetitiveEventImpl {
-                        event_text: row.get(1).unwrap(),
-                        event_start_time: Utc
-                            .timestamp_opt(row.get(2).unwrap(), 0)
-                            .single()
-                            .expect("don't have time"),
-                        event_wait_time: chrono::Duration::seconds(row.get(3).unwrap()),
-                    }),
-                    row.get(0).unwrap(),
-                ))
+                Ok(rep_event_from_row(row)
+                    .map(|ev| (Command::RepetitiveEvent(ev), row.get(0).unwrap())))
             })
             .expect("error in query map");
 
-        for command in command_iter {
-            result.push(command.unwrap());
+        for command in command_iter.flat_map(|c| c.unwrap()) {
+            result.push(command);
         }
 
         result
     }
 
+    /// Series as stored, `event_timezone` stays `None` for the user's zone.
+    pub fn get_rep_event(&self, id: i64, uid: i64) -> Option<RepetitiveEventImpl> {
+        self.conn
+            .query_row(sql_q::SELECT_REP_BY_ID_AND_UID, [&id, &uid], |row| {
+                Ok(rep_event_from_row(row))
+            })
+            .ok()
+            .flatten()
+    }
+
     pub fn delete_rep_event(&mut self, event_id: i64) -> bool {
         if self
             .conn
@@ -212,11 +541,199 @@ impl DataBase {
         true
     }
 
-    pub fn get_user_timezone(&self, uid: i64) -> i32 {
-        let row = self
+    pub fn get_user_timezone(&self, uid: i64) -> Tz {
+        let name = self
             .conn
             .query_row(sql_q::GET_USER_TIMEZONE, [&uid], |row| row.get(0));
-        row.unwrap()
+        timezone_from_column(name.unwrap())
+    }
+
+    pub fn set_user_timezone(&mut self, uid: i64, tz: Tz) -> Result<()> {
+        self.conn
+            .execute(sql_q::UPDATE_USER_TIMEZONE, params![&uid, &tz.name()])
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    pub fn get_user_language(&self, uid: i64) -> Lang {
+        let code: Option<String> = self
+            .conn
+            .query_row(sql_q::GET_USER_LANGUAGE, [&uid], |row| row.get(0))
+            .unwrap_or(None);
+        Lang::from_code(code.as_deref())
+    }
+
+    pub fn set_user_language(&mut self, uid: i64, lang: Lang) -> Result<()> {
+        self.conn
+            .execute(sql_q::UPDATE_USER_LANGUAGE, params![&uid, &lang.code()])
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    pub fn get_user_date_order(&self, uid: i64) -> DateOrder {
+        let code: Option<String> = self
+            .conn
+            .query_row(sql_q::GET_USER_DATE_ORDER, [&uid], |row| row.get(0))
+            .unwrap_or(None);
+        DateOrder::from_code(code.as_deref())
+    }
+
+    pub fn set_user_date_order(&mut self, uid: i64, order: DateOrder) -> Result<()> {
+        self.conn
+            .execute(sql_q::UPDATE_USER_DATE_ORDER, params![&uid, &order.code()])
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    pub fn get_user_fuzzy(&self, uid: i64) -> bool {
+        let enabled: Option<bool> = self
+            .conn
+            .query_row(sql_q::GET_USER_FUZZY, [&uid], |row| row.get(0))
+            .unwrap_or(None);
+        enabled.unwrap_or(false)
+    }
+
+    pub fn set_user_fuzzy(&mut self, uid: i64, enabled: bool) -> Result<()> {
+        self.conn
+            .execute(sql_q::UPDATE_USER_FUZZY, params![&uid, &enabled])
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    pub fn get_user_nag(&self, uid: i64) -> NagSettings {
+        let default = NagSettings::default();
+        self.conn
+            .query_row(sql_q::GET_USER_NAG, [&uid], |row| {
+                Ok(NagSettings {
+                    interval_minutes: row
+                        .get::<_, Option<u32>>(0)?
+                        .unwrap_or(default.interval_minutes),
+                    max_repeats: row.get::<_, Option<u32>>(1)?.unwrap_or(default.max_repeats),
+                })
+            })
+            .unwrap_or(default)
+    }
+
+    pub fn set_user_nag(&mut self, uid: i64, nag: NagSettings) -> Result<()> {
+        self.conn
+            .execute(
+                sql_q::UPDATE_USER_NAG,
+                params![&uid, &nag.interval_minutes, &nag.max_repeats],
+            )
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    pub fn get_user_quiet(&self, uid: i64) -> Option<QuietHours> {
+        let minutes: (Option<u32>, Option<u32>) = self
+            .conn
+            .query_row(sql_q::GET_USER_QUIET, [&uid], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap_or((None, None));
+        let time = |minutes: u32| NaiveTime::from_hms_opt(minutes / 60, minutes % 60, 0);
+        match minutes {
+            (Some(start), Some(end)) => Some(QuietHours {
+                start: time(start)?,
+                end: time(end)?,
+            }),
+            _ => None,
+        }
+    }
+
+    /// `None` turns quiet hours off.
+    pub fn set_user_quiet(&mut self, uid: i64, quiet: Option<QuietHours>) -> Result<()> {
+        let minutes = |time: NaiveTime| time.num_seconds_from_midnight() / 60;
+        self.conn
+            .execute(
+                sql_q::UPDATE_USER_QUIET,
+                params![
+                    &uid,
+                    &quiet.map(|q| minutes(q.start)),
+                    &quiet.map(|q| minutes(q.end))
+                ],
+            )
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    pub fn get_user_digest(&self, uid: i64) -> Option<NaiveTime> {
+        let minutes: Option<u32> = self
+            .conn
+            .query_row(sql_q::GET_USER_DIGEST, [&uid], |row| row.get(0))
+            .unwrap_or(None);
+        minutes.and_then(|minutes| NaiveTime::from_hms_opt(minutes / 60, minutes % 60, 0))
+    }
+
+    /// `None` turns the digest off. Called again when the user's zone changes.
+    pub fn set_user_digest(
+        &mut self,
+        uid: i64,
+        time: Option<NaiveTime>,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        let tz = self.get_user_timezone(uid);
+        self.conn
+            .execute(
+                sql_q::UPDATE_USER_DIGEST,
+                params![
+                    &uid,
+                    &time.map(|time| time.num_seconds_from_midnight() / 60),
+                    &time.map(|time| next_local_time(time, now, &tz).timestamp())
+                ],
+            )
+            .map(|_| ())
+            .map_err(Into::into)
+    }
+
+    /// Users whose digest is due, the next one is scheduled for tomorrow.
+    pub fn extract_digests(&mut self, time: DateTime<Utc>) -> Vec<i64> {
+        let mut stmt = self
+            .conn
+            .prepare(sql_q::SELECT_DIGESTS_LESS_BY_TIMESTAMP)
+            .expect("error in sql query");
+        let uids: Vec<i64> = stmt
+            .query_map([&time.timestamp()], |row| row.get(0))
+            .expect("error in query map")
+            .map(|uid| uid.unwrap())
+            .collect();
+        drop(stmt);
+
+        for uid in &uids {
+            let digest = self.get_user_digest(*uid);
+            if let Err(e) = self.set_user_digest(*uid, digest, time) {
+                error!("Can't schedule digest for user {uid}. Reason: {e}");
+            }
+        }
+        uids
+    }
+
+    /// Pending one-time events and snoozed occurrences in `[from, to)`, the
+    /// rest of series occurrences comes from their rules.
+    pub fn get_one_time_events_between(
+        &self,
+        uid: i64,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<OneTimeEventImpl> {
+        let mut stmt = self
+            .conn
+            .prepare(sql_q::SELECT_ONE_TIME_EVENTS_BETWEEN)
+            .expect("error in sql connection prepare");
+        stmt.query_map(params![&uid, &from.timestamp(), &to.timestamp()], |row| {
+            Ok(OneTimeEventImpl {
+                event_text: row.get(0)?,
+                event_time: Utc
+                    .timestamp_opt(row.get(1)?, 0)
+                    .single()
+                    .expect("don't have time"),
+                event_timezone: None,
+                event_leads: Vec::new(),
+            })
+        })
+        .expect("error in query map")
+        .map(|event| event.unwrap())
+        .collect()
     }
 
     pub fn get_user_chat_id_all(&self) -> Vec<i32> {
@@ -235,16 +752,65 @@ impl DataBase {
         result
     }
 
-    fn put_one_time_event(&mut self, uid: i64, parent_id: i64, command: &OneTimeEventImpl) -> bool {
+    fn put_one_time_event(
+        &mut self,
+        uid: i64,
+        parent_id: i64,
+        command: &OneTimeEventImpl,
+        nag_left: Option<u32>,
+        now: DateTime<Utc>,
+    ) -> bool {
         let event_time = command.event_time.timestamp();
         let res = self.conn.execute(
             sql_q::INSERT_ACTIVE_EVENT,
-            params![&command.event_text, &event_time, &uid, &parent_id],
+            params![
+                &command.event_text,
+                &event_time,
+                &uid,
+                &parent_id,
+                &nag_left
+            ],
         );
         if let Err(e) = res {
             error!("Can't insert one time event in db. Reason: {e}");
             return false;
         }
+
+        let id = self.conn.last_insert_rowid();
+        self.put_lead_alerts(uid, parent_id, id, command, now)
+    }
+
+    /// Alerts before the event in row `id` that are still ahead.
+    fn put_lead_alerts(
+        &mut self,
+        uid: i64,
+        parent_id: i64,
+        id: i64,
+        command: &OneTimeEventImpl,
+        now: DateTime<Utc>,
+    ) -> bool {
+        for lead in &command.event_leads {
+            let alert_time = command.event_time - *lead;
+            // too late to warn in advance
+            if alert_time <= now {
+                continue;
+            }
+            let res = self.conn.execute(
+                sql_q::INSERT_LEAD_ALERT,
+                params![
+                    &command.event_text,
+                    &alert_time.timestamp(),
+                    &uid,
+                    &parent_id,
+                    &id,
+                    &lead.num_seconds()
+                ],
+            );
+            if let Err(e) = res {
+                error!("Can't insert lead alert in db. Reason: {e}");
+                return false;
+            }
+        }
         true
     }
 
@@ -252,13 +818,25 @@ impl DataBase {
         &mut self,
         uid: i64,
         command: &RepetitiveEventImpl,
+        nag_left: Option<u32>,
         now: DateTime<Utc>,
     ) -> bool {
         let event_time: i64 = command.event_start_time.timestamp();
-        let event_wait: i64 = command.event_wait_time.num_seconds();
+        let (event_wait, event_rule) = command.event_rule.to_db();
         let res = self.conn.execute(
             sql_q::INSERT_REP_EVENT,
-            params![&command.event_text, &event_time, &event_wait, &uid],
+            params![
+                &command.event_text,
+                &event_time,
+                &event_wait,
+                &event_rule,
+                &uid,
+                &command.event_until.map(|until| until.timestamp()),
+                &command.event_count,
+                &command.event_timezone.map(|tz| tz.name()),
+                &nag_left.map(|_| true),
+                &leads_to_column(&command.event_leads)
+            ],
         );
         if let Err(e) = res {
             error!("Can't insert repetitive event in db. Reasone: {e}");
@@ -266,44 +844,342 @@ impl DataBase {
         }
 
         let id = self.conn.last_insert_rowid();
+        self.put_first_occurrence(uid, id, command, nag_left, now)
+    }
+
+    fn put_first_occurrence(
+        &mut self,
+        uid: i64,
+        parent_id: i64,
+        command: &RepetitiveEventImpl,
+        nag_left: Option<u32>,
+        now: DateTime<Utc>,
+    ) -> bool {
         let active_event = create_nearest_active_event_from_repetitive(
             command.event_start_time.timestamp(),
-            command.event_wait_time.num_seconds(),
+            &command.event_rule,
             command.event_text.clone(),
+            command.event_leads.clone(),
+            &command
+                .event_timezone
+                .unwrap_or_else(|| self.get_user_timezone(uid)),
             now,
         );
 
-        self.put_one_time_event(uid, id, &active_event)
+        self.put_one_time_event(uid, parent_id, &active_event, nag_left, now)
+    }
+
+    /// Event that has not fired yet and the id of its series, -1 for a
+    /// one-time event.
+    pub fn get_pending_event(&self, id: i64, uid: i64) -> Option<(OneTimeEventImpl, i64)> {
+        self.conn
+            .query_row(sql_q::SELECT_PENDING_EVENT, [&id, &uid], |row| {
+                Ok((
+                    OneTimeEventImpl {
+                        event_text: row.get(0)?,
+                        event_time: Utc
+                            .timestamp_opt(row.get(1)?, 0)
+                            .single()
+                            .expect("don't have time"),
+                        event_timezone: None,
+                        event_leads: Vec::new(),
+                    },
+                    row.get(2)?,
+                ))
+            })
+            .ok()
+    }
+
+    /// Moves a pending one-time event, its lead alerts are made anew.
+    pub fn update_one_time_event(
+        &mut self,
+        id: i64,
+        uid: i64,
+        command: &OneTimeEventImpl,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let res = self.conn.execute(
+            sql_q::UPDATE_PENDING_EVENT,
+            params![
+                &id,
+                &uid,
+                &command.event_text,
+                &command.event_time.timestamp()
+            ],
+        );
+        match res {
+            Ok(1) => {}
+            Ok(_) => return false,
+            Err(e) => {
+                error!("Can't update event {id}. Reason: {e}");
+                return false;
+            }
+        }
+        if let Err(e) = self.conn.execute(sql_q::DELETE_PENDING_LEAD_ALERTS, [&id]) {
+            error!("Can't remove lead alerts of event {id}. Reason: {e}");
+            return false;
+        }
+        self.put_lead_alerts(uid, -1, id, command, now)
+    }
+
+    /// Changes the rule of a series, its pending occurrence is made anew.
+    /// Persistent series stay persistent.
+    pub fn update_repetitive_event(
+        &mut self,
+        parent_id: i64,
+        uid: i64,
+        command: &RepetitiveEventImpl,
+        now: DateTime<Utc>,
+    ) -> bool {
+        let (event_wait, event_rule) = command.event_rule.to_db();
+        let res = self.conn.execute(
+            sql_q::UPDATE_REP_EVENT,
+            params![
+                &parent_id,
+                &uid,
+                &command.event_text,
+                &command.event_start_time.timestamp(),
+                &event_wait,
+                &event_rule,
+                &command.event_until.map(|until| until.timestamp()),
+                &command.event_count,
+                &command.event_timezone.map(|tz| tz.name()),
+                &leads_to_column(&command.event_leads)
+            ],
+        );
+        match res {
+            Ok(1) => {}
+            Ok(_) => return false,
+            Err(e) => {
+                error!("Can't update repetitive event {parent_id}. Reason: {e}");
+                return false;
+            }
+        }
+        let nag: Option<bool> = self
+            .conn
+            .query_row(sql_q::SELECT_REP_BY_ID, [&parent_id], |row| row.get(8))
+            .unwrap_or(None);
+        let nag_left = nag
+            .unwrap_or(false)
+            .then(|| self.get_user_nag(uid).max_repeats);
+        if let Err(e) = self
+            .conn
+            .execute(sql_q::DELETE_PENDING_BY_PARENT_ID, [&parent_id])
+        {
+            error!("Can't remove occurrences of event {parent_id}. Reason: {e}");
+            return false;
+        }
+        self.put_first_occurrence(uid, parent_id, command, nag_left, now)
+    }
+
+    /// New text for a pending event, for the whole series if it is a part of one.
+    pub fn update_event_text(&mut self, id: i64, uid: i64, text: &str) -> bool {
+        let Some((_, parent_id)) = self.get_pending_event(id, uid) else {
+            return false;
+        };
+        let res = if parent_id == -1 {
+            self.conn
+                .execute(sql_q::UPDATE_PENDING_EVENT_TEXT, params![&id, &uid, &text])
+        } else {
+            self.conn
+                .execute(sql_q::UPDATE_REP_TEXT, params![&parent_id, &text])
+                .and_then(|_| {
+                    self.conn.execute(
+                        sql_q::UPDATE_PENDING_TEXT_BY_PARENT_ID,
+                        params![&parent_id, &text],
+                    )
+                })
+        };
+        if let Err(e) = res {
+            error!("Can't update text of event {id}. Reason: {e}");
+            return false;
+        }
+        true
+    }
+
+    /// Removes a pending one-time event with its lead alerts.
+    pub fn delete_pending_event(&mut self, id: i64, uid: i64) -> bool {
+        match self
+            .conn
+            .execute(sql_q::DELETE_PENDING_EVENT, params![&id, &uid])
+        {
+            Ok(deleted) => deleted > 0,
+            Err(e) => {
+                error!("Can't delete event {id}. Reason: {e}");
+                false
+            }
+        }
+    }
+
+    /// Whether the pending event is sent again until done, like its series.
+    pub fn is_pending_event_persistent(&self, id: i64, uid: i64) -> bool {
+        self.conn
+            .query_row(sql_q::SELECT_PENDING_EVENT_NAG, [&id, &uid], |row| {
+                row.get(0)
+            })
+            .unwrap_or(false)
+    }
+
+    /// Ends the series, its fired occurrences still wait for done or snooze.
+    pub fn end_rep_event(&mut self, parent_id: i64, uid: i64) -> bool {
+        let res = self
+            .conn
+            .execute(sql_q::DELETE_FROM_REP_BY_ID_AND_UID, [&parent_id, &uid])
+            .and_then(|deleted| {
+                self.conn
+                    .execute(sql_q::DELETE_PENDING_BY_PARENT_ID, [&parent_id])
+                    .map(|_| deleted)
+            });
+        match res {
+            Ok(deleted) => deleted == 1,
+            Err(e) => {
+                error!("Can't end repetitive event {parent_id}. Reason: {e}");
+                false
+            }
+        }
     }
 } // impl DataBase
 
+fn migrate(conn: &Connection, fresh: bool) {
+    let target = sql_q::MIGRATIONS.len() as i64;
+    let version: i64 = if fresh {
+        target
+    } else {
+        conn.pragma_query_value(None, "user_version", |row| row.get(0))
+            .expect("Cannot read schema version")
+    };
+    for (i, migration) in sql_q::MIGRATIONS.iter().enumerate().skip(version as usize) {
+        info!("Apply database migration #{}", i + 1);
+        conn.execute_batch(migration)
+            .expect("Cannot apply database migration");
+    }
+    conn.pragma_update(None, "user_version", target)
+        .expect("Cannot update schema version");
+}
+
 fn create_nearest_active_event_from_repetitive(
     start_time: i64,
-    wait_time: i64,
+    rule: &Recurrence,
     text: String,
+    leads: Vec<chrono::Duration>,
+    tz: &Tz,
     now: DateTime<Utc>,
 ) -> OneTimeEventImpl {
-    let wait_time = if wait_time < 0 { 1 } else { wait_time }; // TODO: make propper error handling
-    let dt = chrono::Duration::seconds(wait_time);
-    let mut event_time = Utc
+    let start_time = Utc
         .timestamp_opt(start_time, 0)
         .single()
         .expect("don't have time");
-
-    while event_time < now {
-        event_time += dt;
-    }
     OneTimeEventImpl {
         event_text: text,
-        event_time,
+        event_time: rule.next_occurrence(start_time, tz, now),
+        event_timezone: None,
+        event_leads: leads,
+    }
+}
+
+/// Row of `SELECT_ALL_REP_BY_UID_LIMIT` and alike, `None` for an unknown rule.
+fn rep_event_from_row(row: &rusqlite::Row) -> Option<RepetitiveEventImpl> {
+    let rule = rule_from_columns(row.get(3).unwrap(), row.get(4).unwrap())?;
+    Some(RepetitiveEventImpl {
+        event_text: row.get(1).unwrap(),
+        event_start_time: Utc
+            .timestamp_opt(row.get(2).unwrap(), 0)
+            .single()
+            .expect("don't have time"),
+        event_rule: rule,
+        event_until: row.get::<_, Option<i64>>(5).unwrap().map(|until| {
+            Utc.timestamp_opt(until, 0)
+                .single()
+                .expect("don't have time")
+        }),
+        event_count: row.get(6).unwrap(),
+        event_timezone: row
+            .get::<_, Option<String>>(7)
+            .unwrap()
+            .map(|name| timezone_from_column(Some(name))),
+        event_leads: leads_from_column(row.get(8).unwrap()),
+    })
+}
+
+/// Lead alerts as comma-separated seconds, `NULL` when there are none.
+fn leads_to_column(leads: &[chrono::Duration]) -> Option<String> {
+    if leads.is_empty() {
+        return None;
     }
+    let seconds: Vec<String> = leads.iter().map(|l| l.num_seconds().to_string()).collect();
+    Some(seconds.join(","))
+}
+
+fn leads_from_column(column: Option<String>) -> Vec<chrono::Duration> {
+    let mut leads: Vec<chrono::Duration> = column
+        .iter()
+        .flat_map(|column| column.split(','))
+        .filter_map(|seconds| {
+            seconds
+                .parse()
+                .inspect_err(|e| warn!("Incorrect lead alert in database: {e}"))
+                .ok()
+        })
+        .map(chrono::Duration::seconds)
+        .collect();
+    leads.sort_by(|a, b| b.cmp(a));
+    leads
+}
+
+/// Nearest moment after `after` when the local clock shows `time`.
+fn next_local_time(time: NaiveTime, after: DateTime<Utc>, tz: &Tz) -> DateTime<Utc> {
+    let local = after.with_timezone(tz).naive_local();
+    let mut next = local.date().and_time(time);
+    if next <= local {
+        next += chrono::Duration::days(1);
+    }
+    local_to_utc(tz, next)
+}
+
+fn rule_from_columns(wait: Option<i64>, rule: Option<String>) -> Option<Recurrence> {
+    let result = Recurrence::from_db(wait, rule.clone());
+    if result.is_none() {
+        error!("Incorrect recurrence rule in database: wait {wait:?}, rule {rule:?}");
+    }
+    result
+}
+
+fn timezone_from_column(name: Option<String>) -> Tz {
+    name.and_then(|name| {
+        name.parse()
+            .inspect_err(|e| warn!("Unknown time zone in database: {e}"))
+            .ok()
+    })
+    .unwrap_or(DEFAULT_TIMEZONE)
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use chrono_tz::{America, Asia, Europe};
     // use crate::command::Command::*;
 
+    /// In-memory base with user 1 in Berlin, speaking English.
+    pub(crate) fn db_with_user() -> DataBase {
+        let mut db = DataBase::new(DbMode::InMemory);
+        let info = UserInfo {
+            uid: 1,
+            name: "name",
+            chat_id: 123,
+            first_name: "first",
+            last_name: "last",
+            tz: Europe::Berlin,
+            lang: Lang::En,
+        };
+        db.add_user(info).unwrap();
+        db
+    }
+
+    /// UTC time in January 2026.
+    pub(crate) fn utc(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, day, hour, minute, 0).unwrap()
+    }
+
     #[test]
     fn add_user() {
         let mut db = DataBase::new(DbMode::InMemory);
@@ -313,10 +1189,11 @@ mod tests {
             chat_id: 123,
             first_name: "first",
             last_name: "last",
-            tz: -1,
+            tz: Europe::Berlin,
+            lang: Lang::En,
         };
         assert!(db.add_user(info).is_ok());
-        assert_eq!(db.get_user_timezone(1), -1);
+        assert_eq!(db.get_user_timezone(1), Europe::Berlin);
         assert_eq!(db.get_user_chat_id_all(), vec!(123));
 
         let info = UserInfo {
@@ -325,11 +1202,26 @@ mod tests {
             chat_id: 1234,
             first_name: "first",
             last_name: "last",
-            tz: -2,
+            tz: Asia::Kolkata,
+            lang: Lang::Ru,
         };
         assert!(db.add_user(info).is_ok());
-        assert_eq!(db.get_user_timezone(2), -2);
+        assert_eq!(db.get_user_timezone(2), Asia::Kolkata);
+        assert_eq!(db.get_user_language(1), Lang::En);
+        assert_eq!(db.get_user_language(2), Lang::Ru);
+        db.set_user_language(1, Lang::Ru).unwrap();
+        assert_eq!(db.get_user_language(1), Lang::Ru);
+        assert_eq!(db.get_user_date_order(1), DateOrder::DayMonth);
+        db.set_user_date_order(1, DateOrder::MonthDay).unwrap();
+        assert_eq!(db.get_user_date_order(1), DateOrder::MonthDay);
+        assert!(!db.get_user_fuzzy(1));
+        db.set_user_fuzzy(1, true).unwrap();
+        assert!(db.get_user_fuzzy(1));
         assert_eq!(db.get_user_chat_id_all(), vec!(123, 1234));
+
+        db.set_user_timezone(2, Europe::London).unwrap();
+        assert_eq!(db.get_user_timezone(2), Europe::London);
+        assert_eq!(db.get_user_timezone(1), Europe::Berlin);
     }
 
     // #[test]
@@ -346,7 +1238,8 @@ mod tests {
             chat_id: 123,
             first_name: "first",
             last_name: "last",
-            tz: -1,
+            tz: America::New_York,
+            lang: Lang::En,
         };
         db.add_user(info).unwrap();
         assert!(db.get_nearest_wakeup().is_none());
@@ -355,6 +1248,8 @@ mod tests {
         let event = Command::OneTimeEvent(OneTimeEventImpl {
             event_text: String::from("test"),
             event_time: Utc.timestamp_opt(61, 0).unwrap(),
+            event_timezone: None,
+            event_leads: Vec::new(),
         });
         db.put(1, event, Utc::now());
         let wake = db.get_nearest_wakeup();
@@ -376,21 +1271,28 @@ mod tests {
             chat_id: 123,
             first_name: "first",
             last_name: "last",
-            tz: -1,
+            tz: America::New_York,
+            lang: Lang::En,
         };
         db.add_user(info).unwrap();
 
         let event1 = Command::OneTimeEvent(OneTimeEventImpl {
             event_text: String::from("test"),
             event_time: Utc.timestamp_opt(61, 0).unwrap(),
+            event_timezone: None,
+            event_leads: Vec::new(),
         });
         let event2 = Command::OneTimeEvent(OneTimeEventImpl {
             event_text: String::from("test"),
             event_time: Utc.timestamp_opt(63, 0).unwrap(),
+            event_timezone: None,
+            event_leads: Vec::new(),
         });
         let event3 = Command::OneTimeEvent(OneTimeEventImpl {
             event_text: String::from("test"),
             event_time: Utc.timestamp_opt(65, 0).unwrap(),
+            event_timezone: None,
+            event_leads: Vec::new(),
         });
         let now = Utc::now();
         db.put(1, event1.clone(), now);
@@ -399,16 +1301,504 @@ mod tests {
 
         let expect = vec![
             RetrieveEventsResult {
+                id: 1,
                 command: event1,
                 uid: 1,
+                lead: None,
             },
             RetrieveEventsResult {
+                id: 2,
                 command: event2,
                 uid: 1,
+                lead: None,
             },
         ];
 
         let events = db.extract_events_happens_already(Utc.timestamp_opt(64, 0).unwrap());
         assert_eq!(events, expect);
     }
+
+    #[test]
+    fn migrate_integer_timezone() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE user(uid INTEGER PRIMARY KEY, username TEXT, first_name TEXT,
+                last_name TEXT, timezone INTEGER, chat_id INTEGER);
+             CREATE TABLE scheduled_event(id INTEGER PRIMARY KEY, uid INTEGER,
+                event_text TEXT, event_time INTEGER, event_wait INTEGER);
+             CREATE TABLE active_event(id INTEGER PRIMARY KEY, uid INTEGER,
+                parent_id INTEGER, event_text TEXT, event_time INTEGER);
+             INSERT INTO user VALUES (1, 'a', '', '', -3, 1);
+             INSERT INTO user VALUES (2, 'b', '', '', 5, 2);",
+        )
+        .unwrap();
+        migrate(&conn, false);
+        let db = DataBase { conn };
+        assert_eq!(db.get_user_timezone(1), chrono_tz::Etc::GMTMinus3);
+        assert_eq!(db.get_user_timezone(2), chrono_tz::Etc::GMTPlus5);
+        assert_eq!(db.get_user_language(1), Lang::En);
+    }
+
+    #[test]
+    fn rep_event_keeps_local_time_across_dst() {
+        let mut db = db_with_user();
+
+        // daily at 09:00 Berlin time, DST starts 2026-03-29
+        let daily = Command::RepetitiveEvent(RepetitiveEventImpl {
+            event_text: String::from("daily"),
+            event_start_time: Utc.with_ymd_and_hms(2026, 3, 28, 8, 0, 0).unwrap(),
+            event_rule: Recurrence::Interval(chrono::Duration::days(1)),
+            event_until: None,
+            event_count: None,
+            event_timezone: None,
+            event_leads: Vec::new(),
+        });
+        db.put(
+            1,
+            daily,
+            Utc.with_ymd_and_hms(2026, 3, 27, 0, 0, 0).unwrap(),
+        );
+
+        let first = Utc.with_ymd_and_hms(2026, 3, 28, 8, 0, 0).unwrap();
+        assert_eq!(db.get_nearest_wakeup(), Some(first));
+        assert_eq!(db.extract_events_happens_already(first).len(), 1);
+        assert_eq!(
+            db.get_nearest_wakeup(),
+            Some(Utc.with_ymd_and_hms(2026, 3, 29, 7, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn snooze_fired_event() {
+        let mut db = db_with_user();
+
+        let daily = Command::RepetitiveEvent(RepetitiveEventImpl {
+            event_text: String::from("daily"),
+            event_start_time: utc(1, 9, 0),
+            event_rule: Recurrence::Interval(chrono::Duration::days(1)),
+            event_until: None,
+            event_count: None,
+            event_timezone: None,
+            event_leads: Vec::new(),
+        });
+        db.put(1, daily, utc(1, 0, 0));
+
+        let fired = db.extract_events_happens_already(utc(1, 9, 0));
+        assert_eq!(fired.len(), 1);
+        let id = fired[0].id;
+        assert_eq!(db.get_all_active_events(1).len(), 1);
+        assert_eq!(db.get_fired_event(id, 1).unwrap().event_text, "daily");
+        // only the owner can snooze
+        assert!(!db.snooze_event(id, 2, utc(1, 10, 0)));
+
+        assert!(db.snooze_event(id, 1, utc(1, 10, 0)));
+        assert!(db.get_fired_event(id, 1).is_none());
+        assert_eq!(db.get_nearest_wakeup(), Some(utc(1, 10, 0)));
+        assert_eq!(db.get_all_active_events(1).len(), 2);
+
+        // the snoozed occurrence fires again without adding one more day
+        let fired = db.extract_events_happens_already(utc(1, 10, 0));
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].id, id);
+        assert_eq!(db.get_all_active_events(1).len(), 1);
+        assert!(db.delete_fired_event(id, 1));
+        assert!(db.get_fired_event(id, 1).is_none());
+
+        // forgotten notifications are dropped after a week
+        db.extract_events_happens_already(utc(1, 0, 0) + chrono::Duration::days(1));
+        let next = db.extract_events_happens_already(utc(1, 9, 0) + chrono::Duration::days(1));
+        assert!(db.get_fired_event(next[0].id, 1).is_some());
+        db.extract_events_happens_already(utc(1, 10, 0) + chrono::Duration::days(8));
+        assert!(db.get_fired_event(next[0].id, 1).is_none());
+    }
+
+    #[test]
+    fn persistent_event_nags_until_done() {
+        let mut db = db_with_user();
+        assert_eq!(db.get_user_nag(1), NagSettings::default());
+        let nag = NagSettings {
+            interval_minutes: 10,
+            max_repeats: 2,
+        };
+        db.set_user_nag(1, nag).unwrap();
+        assert_eq!(db.get_user_nag(1), nag);
+
+        let pills = Command::OneTimeEvent(OneTimeEventImpl {
+            event_text: String::from("pills"),
+            event_time: utc(1, 9, 0),
+            event_timezone: None,
+            event_leads: Vec::new(),
+        });
+        db.put_persistent(1, pills, utc(1, 9, 0) - chrono::Duration::hours(1));
+
+        let id = db.extract_events_happens_already(utc(1, 9, 0))[0].id;
+        assert_eq!(db.get_nearest_wakeup(), Some(utc(1, 9, 10)));
+        assert!(db.extract_events_happens_already(utc(1, 9, 9)).is_empty());
+        let again = db.extract_events_happens_already(utc(1, 9, 10));
+        assert_eq!(again.len(), 1);
+        assert_eq!(again[0].id, id);
+        assert_eq!(db.get_nearest_wakeup(), Some(utc(1, 9, 20)));
+        assert_eq!(db.extract_events_happens_already(utc(1, 9, 20)).len(), 1);
+        // out of repeats, still waits for Done
+        assert!(db.get_nearest_wakeup().is_none());
+        assert!(db.get_fired_event(id, 1).is_some());
+
+        // a button makes a fired event persistent, Done stops it
+        assert!(db.start_nag(id, 1, utc(1, 9, 30)));
+        assert_eq!(db.get_nearest_wakeup(), Some(utc(1, 9, 40)));
+        assert!(db.delete_fired_event(id, 1));
+        assert!(db.get_nearest_wakeup().is_none());
+    }
+
+    #[test]
+    fn lead_alerts_fire_before_event() {
+        let mut db = db_with_user();
+
+        let leads = vec![chrono::Duration::hours(1), chrono::Duration::minutes(15)];
+        let standup = |leads: Vec<chrono::Duration>| OneTimeEventImpl {
+            event_text: String::from("standup"),
+            event_time: utc(1, 12, 30),
+            event_timezone: None,
+            event_leads: leads,
+        };
+        // an hour before is already past, only the 15 minutes alert is kept
+        db.put(1, Command::OneTimeEvent(standup(leads)), utc(1, 12, 0));
+        assert_eq!(
+            db.get_all_active_events(1),
+            vec![(
+                Command::OneTimeEvent(standup(vec![chrono::Duration::minutes(15)])),
+                None,
+                1
+            )]
+        );
+        assert_eq!(db.get_nearest_wakeup(), Some(utc(1, 12, 15)));
+
+        let alert = db.extract_events_happens_already(utc(1, 12, 15));
+        assert_eq!(alert.len(), 1);
+        assert_eq!(alert[0].lead, Some(chrono::Duration::minutes(15)));
+        assert!(db.get_fired_event(alert[0].id, 1).is_none());
+        assert_eq!(
+            db.get_all_active_events(1),
+            vec![(Command::OneTimeEvent(standup(Vec::new())), None, 1)]
+        );
+        let event = db.extract_events_happens_already(utc(1, 12, 30));
+        assert_eq!(event.len(), 1);
+        assert_eq!(event[0].lead, None);
+        assert!(db.get_all_active_events(1).is_empty());
+        // a snoozed event comes back alone
+        assert!(db.snooze_event(event[0].id, 1, utc(1, 12, 45)));
+        let event = db.extract_events_happens_already(utc(1, 12, 45));
+        assert_eq!(event.len(), 1);
+        assert_eq!(event[0].lead, None);
+
+        // every occurrence of a series gets its alerts, the next one is
+        // scheduled when the occurrence fires, not its alert
+        let daily = Command::RepetitiveEvent(RepetitiveEventImpl {
+            event_text: String::from("standup"),
+            event_start_time: utc(1, 12, 30),
+            event_rule: Recurrence::Interval(chrono::Duration::days(1)),
+            event_until: None,
+            event_count: None,
+            event_timezone: None,
+            event_leads: vec![chrono::Duration::minutes(10)],
+        });
+        db.put(1, daily, utc(1, 12, 0));
+        let alert = db.extract_events_happens_already(utc(1, 12, 20));
+        assert_eq!(alert[0].lead, Some(chrono::Duration::minutes(10)));
+        assert_eq!(db.get_all_active_events(1).len(), 1);
+        assert_eq!(db.extract_events_happens_already(utc(1, 12, 30)).len(), 1);
+        let next_alert = utc(1, 12, 20) + chrono::Duration::days(1);
+        assert_eq!(db.get_nearest_wakeup(), Some(next_alert));
+        match &db.get_all_rep_events(1)[0].0 {
+            Command::RepetitiveEvent(ev) => {
+                assert_eq!(ev.event_leads, vec![chrono::Duration::minutes(10)])
+            }
+            res => panic!("Unexpected result: {res:?}"),
+        }
+    }
+
+    #[test]
+    fn quiet_hours_hold_back_events() {
+        let mut db = db_with_user();
+        assert_eq!(db.get_user_quiet(1), None);
+        // 23.00-08.00 Berlin time is 22.00-07.00 UTC in winter
+        let quiet = QuietHours {
+            start: NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+        };
+        db.set_user_quiet(1, Some(quiet)).unwrap();
+        assert_eq!(db.get_user_quiet(1), Some(quiet));
+
+        let call = Command::OneTimeEvent(OneTimeEventImpl {
+            event_text: String::from("call"),
+            event_time: utc(2, 2, 0),
+            event_timezone: None,
+            event_leads: Vec::new(),
+        });
+        db.put(1, call, utc(2, 0, 0));
+        assert!(db.extract_events_happens_already(utc(2, 2, 0)).is_empty());
+        assert_eq!(db.get_nearest_wakeup(), Some(utc(2, 7, 0)));
+        assert_eq!(db.extract_events_happens_already(utc(2, 7, 0)).len(), 1);
+
+        // frequent repeats are skipped, the series goes on after the quiet hours
+        let water = Command::RepetitiveEvent(RepetitiveEventImpl {
+            event_text: String::from("water"),
+            event_start_time: utc(2, 6, 30),
+            event_rule: Recurrence::Interval(chrono::Duration::minutes(15)),
+            event_until: None,
+            event_count: None,
+            event_timezone: None,
+            event_leads: Vec::new(),
+        });
+        db.put(1, water, utc(2, 6, 0));
+        assert!(db.extract_events_happens_already(utc(2, 6, 30)).is_empty());
+        assert_eq!(db.get_nearest_wakeup(), Some(utc(2, 7, 0)));
+        let events = db.extract_events_happens_already(utc(2, 7, 0));
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].command,
+            Command::OneTimeEvent(OneTimeEventImpl {
+                event_text: String::from("water"),
+                event_time: utc(2, 7, 0),
+                event_timezone: None,
+                event_leads: Vec::new(),
+            })
+        );
+
+        db.set_user_quiet(1, None).unwrap();
+        assert_eq!(db.get_user_quiet(1), None);
+    }
+
+    #[test]
+    fn digest_is_scheduled_daily() {
+        let mut db = db_with_user();
+
+        let eight = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+        assert_eq!(db.get_user_digest(1), None);
+        db.set_user_digest(1, Some(eight), utc(2, 10, 0)).unwrap();
+        assert_eq!(db.get_user_digest(1), Some(eight));
+        // 8.00 Berlin time is 7.00 UTC, today's one has passed
+        assert_eq!(db.get_nearest_wakeup(), Some(utc(3, 7, 0)));
+        assert!(db.extract_digests(utc(3, 6, 0)).is_empty());
+        assert_eq!(db.extract_digests(utc(3, 7, 0)), vec![1]);
+        assert_eq!(db.get_nearest_wakeup(), Some(utc(4, 7, 0)));
+
+        // a reminder wakes the bot up earlier, the digest lists it
+        let call = OneTimeEventImpl {
+            event_text: String::from("call"),
+            event_time: utc(3, 15, 0),
+            event_timezone: None,
+            event_leads: Vec::new(),
+        };
+        db.put(1, Command::OneTimeEvent(call.clone()), utc(3, 7, 0));
+        assert_eq!(db.get_nearest_wakeup(), Some(utc(3, 15, 0)));
+        assert_eq!(
+            db.get_one_time_events_between(1, utc(3, 7, 0), utc(3, 23, 0)),
+            vec![call]
+        );
+        assert!(
+            db.get_one_time_events_between(1, utc(4, 0, 0), utc(4, 23, 0))
+                .is_empty()
+        );
+
+        db.set_user_digest(1, None, utc(3, 8, 0)).unwrap();
+        assert_eq!(db.get_user_digest(1), None);
+        assert_eq!(db.get_nearest_wakeup(), Some(utc(3, 15, 0)));
+    }
+
+    #[test]
+    fn edit_events_in_place() {
+        let mut db = db_with_user();
+
+        let now = utc(1, 8, 0);
+        let call = OneTimeEventImpl {
+            event_text: String::from("call"),
+            event_time: utc(1, 12, 0),
+            event_timezone: None,
+            event_leads: Vec::new(),
+        };
+        db.put(1, Command::OneTimeEvent(call.clone()), now);
+        let id = db.get_all_active_events(1)[0].2;
+        assert_eq!(db.get_pending_event(id, 1), Some((call.clone(), -1)));
+        // somebody else's reminder is not found
+        assert_eq!(db.get_pending_event(id, 2), None);
+
+        let moved = OneTimeEventImpl {
+            event_text: String::from("call mom"),
+            event_time: utc(1, 15, 0),
+            event_timezone: None,
+            event_leads: vec![chrono::Duration::hours(1)],
+        };
+        assert!(db.update_one_time_event(id, 1, &moved, now));
+        assert_eq!(db.get_nearest_wakeup(), Some(utc(1, 14, 0)));
+        assert!(db.update_event_text(id, 1, "call dad"));
+        let alert = db.extract_events_happens_already(utc(1, 14, 0));
+        assert_eq!(alert.len(), 1);
+        assert!(
+            matches!(&alert[0].command, Command::OneTimeEvent(ev) if ev.event_text == "call dad")
+        );
+        assert_eq!(
+            db.get_pending_event(id, 1).map(|(ev, _)| ev.event_time),
+            Some(utc(1, 15, 0))
+        );
+        assert!(db.delete_pending_event(id, 1));
+        assert_eq!(db.get_pending_event(id, 1), None);
+        assert!(!db.update_event_text(id, 1, "gone"));
+        assert_eq!(db.get_nearest_wakeup(), None);
+
+        let daily = RepetitiveEventImpl {
+            event_text: String::from("tea"),
+            event_start_time: utc(2, 9, 0),
+            event_rule: Recurrence::Interval(chrono::Duration::days(1)),
+            event_until: None,
+            event_count: None,
+            event_timezone: None,
+            event_leads: Vec::new(),
+        };
+        db.put(1, Command::RepetitiveEvent(daily.clone()), now);
+        let id = db.get_all_active_events(1)[0].2;
+        let (_, parent_id) = db.get_pending_event(id, 1).unwrap();
+        assert_ne!(parent_id, -1);
+
+        let evening = RepetitiveEventImpl {
+            event_start_time: utc(2, 18, 0),
+            ..daily
+        };
+        assert!(db.update_repetitive_event(parent_id, 1, &evening, now));
+        assert_eq!(db.get_nearest_wakeup(), Some(utc(2, 18, 0)));
+        let id = db.get_all_active_events(1)[0].2;
+        assert!(db.update_event_text(id, 1, "green tea"));
+        assert_eq!(
+            db.get_all_rep_events(1).first().map(|r| r.0.clone()),
+            Some(Command::RepetitiveEvent(RepetitiveEventImpl {
+                event_text: String::from("green tea"),
+                ..evening
+            }))
+        );
+    }
+
+    #[test]
+    fn rep_event_calendar_rule() {
+        let mut db = db_with_user();
+
+        // 31 Jan 10:00 local, every month
+        let start = Utc.with_ymd_and_hms(2026, 1, 31, 9, 0, 0).unwrap();
+        let rent = Command::RepetitiveEvent(RepetitiveEventImpl {
+            event_text: String::from("rent"),
+            event_start_time: start,
+            event_rule: Recurrence::Months(1),
+            event_until: None,
+            event_count: None,
+            event_timezone: None,
+            event_leads: Vec::new(),
+        });
+        db.put(
+            1,
+            rent.clone(),
+            Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap(),
+        );
+        assert_eq!(db.get_all_rep_events(1).first().map(|r| &r.0), Some(&rent));
+
+        assert_eq!(db.extract_events_happens_already(start).len(), 1);
+        let feb = Utc.with_ymd_and_hms(2026, 2, 28, 9, 0, 0).unwrap();
+        assert_eq!(db.get_nearest_wakeup(), Some(feb));
+        assert_eq!(db.extract_events_happens_already(feb).len(), 1);
+        assert_eq!(
+            db.get_nearest_wakeup(),
+            Some(Utc.with_ymd_and_hms(2026, 3, 31, 8, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn rep_event_series_end() {
+        let mut db = db_with_user();
+
+        let twice = Command::RepetitiveEvent(RepetitiveEventImpl {
+            event_text: String::from("twice"),
+            event_start_time: utc(1, 9, 0),
+            event_rule: Recurrence::Interval(chrono::Duration::days(1)),
+            event_until: None,
+            event_count: Some(2),
+            event_timezone: None,
+            event_leads: Vec::new(),
+        });
+        let until = Command::RepetitiveEvent(RepetitiveEventImpl {
+            event_text: String::from("until"),
+            event_start_time: utc(1, 9, 0),
+            event_rule: Recurrence::Interval(chrono::Duration::days(2)),
+            event_until: Some(utc(4, 9, 0)),
+            event_count: None,
+            event_timezone: None,
+            event_leads: Vec::new(),
+        });
+        db.put(1, twice, utc(1, 9, 0));
+        db.put(1, until, utc(1, 9, 0));
+        assert_eq!(db.get_all_rep_events(1).len(), 2);
+
+        assert_eq!(db.extract_events_happens_already(utc(1, 9, 0)).len(), 2);
+        assert_eq!(db.get_all_active_events(1).len(), 2);
+        let counts: Vec<_> = db
+            .get_all_rep_events(1)
+            .into_iter()
+            .map(|(command, _)| match command {
+                Command::RepetitiveEvent(ev) => (ev.event_text, ev.event_count),
+                _ => panic!("Wrong command type"),
+            })
+            .collect();
+        assert!(counts.contains(&("twice".to_owned(), Some(1))));
+
+        // last one of "twice", "until" goes on to 3 Jan
+        assert_eq!(db.extract_events_happens_already(utc(2, 9, 0)).len(), 1);
+        assert_eq!(db.get_all_rep_events(1).len(), 1);
+        assert_eq!(db.get_nearest_wakeup(), Some(utc(3, 9, 0)));
+
+        // 5 Jan is past the end
+        assert_eq!(db.extract_events_happens_already(utc(3, 9, 0)).len(), 1);
+        assert!(db.get_all_rep_events(1).is_empty());
+        assert!(db.get_nearest_wakeup().is_none());
+    }
+
+    #[test]
+    fn rep_event_timezone_round_trip() {
+        let mut db = db_with_user();
+        let march = |d, h| Utc.with_ymd_and_hms(2026, 3, d, h, 0, 0).unwrap();
+
+        // 9:00 every day in Tokyo and in the user's Berlin, which moves to
+        // summer time on 29 March
+        let daily = |text: &str, start, event_timezone| RepetitiveEventImpl {
+            event_text: text.to_owned(),
+            event_start_time: start,
+            event_rule: Recurrence::Interval(chrono::Duration::days(1)),
+            event_until: None,
+            event_count: None,
+            event_timezone,
+            event_leads: Vec::new(),
+        };
+        let tokyo = daily("tokyo", march(28, 0), Some(Asia::Tokyo));
+        let local = daily("local", march(28, 8), None);
+        db.put(1, Command::RepetitiveEvent(tokyo.clone()), march(27, 12));
+        db.put(1, Command::RepetitiveEvent(local.clone()), march(27, 12));
+
+        let reloaded: Vec<_> = db.get_all_rep_events(1).into_iter().map(|r| r.0).collect();
+        assert_eq!(
+            reloaded,
+            vec![
+                Command::RepetitiveEvent(tokyo),
+                Command::RepetitiveEvent(local)
+            ]
+        );
+
+        // next occurrences come from SELECT_REP_BY_ID, NULL zone is the user's
+        assert_eq!(db.extract_events_happens_already(march(28, 8)).len(), 2);
+        let mut times: Vec<_> = db
+            .get_all_active_events(1)
+            .into_iter()
+            .map(|(command, _, _)| match command {
+                Command::OneTimeEvent(ev) => ev.event_time,
+                _ => panic!("Wrong command type"),
+            })
+            .collect();
+        times.sort();
+        assert_eq!(times, vec![march(29, 0), march(29, 7)]);
+    }
 }
@@ -5,7 +5,11 @@ use std::collections::HashMap;
 
 use crate::command::*;
 use crate::database::{DataBase, DbMode, UserInfo};
+use crate::helpers::format_digest;
+use crate::recurrence::format_duration;
 use crate::state::*;
+use crate::text_data::Lang;
+use crate::timezone::{describe_timezone, timezone_by_location};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct CmdFromEngine {
@@ -84,6 +88,32 @@ impl Engine {
         Ok(frontend_command)
     }
 
+    pub fn handle_location(
+        &mut self,
+        uid: i64,
+        latitude: f64,
+        longitude: f64,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<FrontendCommand>> {
+        info!("handle location for {uid}");
+        self.user_states
+            .get(&(uid as i32))
+            .context("no /start command was processed")?;
+        let lang = self.data_base.get_user_language(uid);
+        let cmd = match timezone_by_location(latitude, longitude) {
+            Some(tz) => FrontendCommand::keyboard(KeyboardCommand {
+                action_type: KeyboardCommandType::TimezoneConfirm(tz),
+                text: lang.timezone_at_location(&describe_timezone(&tz, now)),
+                lang,
+            }),
+            None => FrontendCommand::send(SendMessageCommand {
+                text: lang.unknown_location_timezone().to_owned(),
+            }),
+        };
+        debug!("send frontend_commands: {:?}", cmd);
+        Ok(vec![cmd])
+    }
+
     pub fn handle_keyboard_responce(
         &mut self,
         uid: i64,
@@ -111,6 +141,12 @@ impl Engine {
                 frontend_command: vec![FrontendCommand::delete_keyboard(msg_id)],
                 next_state: None,
             }),
+            // fired reminders come at any moment, whatever the dialog state
+            cb if cb.starts_with("fired:") => {
+                process_fired_keyboard(data, now, &mut self.data_base)
+            }
+            // listings stay in the chat, so do their buttons
+            cb if cb.starts_with("edit:") => process_edit_keyboard(data, &mut self.data_base),
             _ => state.process_keyboard(data, now, &mut self.data_base),
         };
         let (front_cmd, next) = match result {
@@ -120,7 +156,10 @@ impl Engine {
             }) => (frontend_command, next_state),
             Err(e) => (
                 vec![FrontendCommand::send(SendMessageCommand {
-                    text: format!("error while processing keyboard, return to default state: {e}"),
+                    text: self
+                        .data_base
+                        .get_user_language(uid)
+                        .keyboard_error(&e.to_string()),
                 })],
                 Some(UserState::ReadyToProcess),
             ),
@@ -132,45 +171,53 @@ impl Engine {
         Ok(front_cmd)
     }
 
-    pub fn add_user(
-        &mut self,
-        uid: i64,
-        username: &str,
-        chat_id: i64,
-        first_name: &str,
-        last_name: &str,
-        tz: i32,
-    ) -> Result<()> {
-        info!("Add new user id - {}, username - {}", uid, username);
-        let user_info = UserInfo {
-            uid,
-            name: username,
-            chat_id,
-            first_name,
-            last_name,
-            tz,
-        };
+    pub fn add_user(&mut self, user_info: UserInfo) -> Result<()> {
+        info!(
+            "Add new user id - {}, username - {}",
+            user_info.uid, user_info.name
+        );
+        let uid = user_info.uid;
         self.data_base.add_user(user_info)?;
         self.user_states
             .insert(uid as i32, UserState::ReadyToProcess);
         Ok(())
     }
 
+    pub fn get_user_language(&self, uid: i64) -> Lang {
+        self.data_base.get_user_language(uid)
+    }
+
     pub fn get_user_chat_id_all(&self) -> Vec<i32> {
         self.data_base.get_user_chat_id_all()
     }
 
     pub fn tick(&mut self, now: DateTime<Utc>) -> Vec<CmdFromEngine> {
         let mut result: Vec<CmdFromEngine> = Vec::new();
+        for uid in self.data_base.extract_digests(now) {
+            let text = format_digest(uid, now, &mut self.data_base);
+            result.push(CmdFromEngine {
+                uid,
+                to_msg: None,
+                cmd_vec: vec![FrontendCommand::send(SendMessageCommand { text })],
+            });
+        }
         for ev in self.data_base.extract_events_happens_already(now) {
             let event_text = match &ev.command {
                 Command::OneTimeEvent(ev) => ev.event_text.clone(),
                 Command::RepetitiveEvent(ev) => ev.event_text.clone(),
             };
-            let cmd = FrontendCommand::keyboard(KeyboardCommand {
-                action_type: KeyboardCommandType::Main,
-                text: event_text,
-            });
+            let lang = self.data_base.get_user_language(ev.uid);
+            // snooze and done belong to the event itself, not its early alerts
+            let cmd = match ev.lead {
+                Some(lead) => FrontendCommand::send(SendMessageCommand {
+                    text: lang.lead_alert(&format_duration(lead, lang), &event_text),
+                }),
+                None => FrontendCommand::keyboard(KeyboardCommand {
+                    action_type: KeyboardCommandType::Fired(ev.id),
+                    text: event_text,
+                    lang,
+                }),
+            };
             result.push(CmdFromEngine {
                 uid: ev.uid,
                 to_msg: None,
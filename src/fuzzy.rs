@@ -0,0 +1,142 @@
+use chrono::prelude::*;
+use chrono_tz::Tz;
+
+use crate::command::{Command, DateOrder, is_word, parse_command};
+
+// longest time phrase tried, `day after tomorrow at 7.30 pm`
+const MAX_PHRASE_WORDS: usize = 7;
+// longer messages are notes rather than reminders
+const MAX_MESSAGE_WORDS: usize = 40;
+
+/// Finds a time phrase anywhere in free text, e.g. `call mom in 2 hours` or
+/// `встреча завтра в 15:00`, using the strict grammar of `parse_command`:
+/// the phrase is moved to the front and the rest of the message has to
+/// become the event text. The longest phrase wins, then the leftmost one.
+/// A bare number before `at` is not taken as a day: in `room 5 at 18`
+/// the number belongs to the text.
+///
+/// Returns the equivalent strict command line together with its parse.
+pub fn extract_command(
+    text: &str,
+    now: DateTime<Utc>,
+    tz: Tz,
+    order: DateOrder,
+) -> Option<(String, Command)> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.len() > MAX_MESSAGE_WORDS {
+        return None;
+    }
+
+    let mut best: Option<(usize, String, Command)> = None;
+    for start in 0..words.len() {
+        for end in start + 1..=words.len().min(start + MAX_PHRASE_WORDS) {
+            let length = end - start;
+            if best.as_ref().is_some_and(|(best, _, _)| *best >= length) {
+                continue;
+            }
+            // every time spec has a number in it
+            let phrase = &words[start..end];
+            if !phrase.iter().any(|w| w.chars().any(|c| c.is_ascii_digit())) {
+                continue;
+            }
+            let phrase = match phrase.split_first() {
+                Some((first, rest)) if is_word(&first.to_lowercase(), |pack| pack.after) => rest,
+                _ => phrase,
+            };
+            if let [first, second, ..] = phrase
+                && first.chars().all(|c| c.is_ascii_digit())
+                && is_word(&second.to_lowercase(), |pack| pack.at)
+            {
+                continue;
+            }
+            let rest = [&words[..start], &words[end..]].concat().join(" ");
+            if rest.is_empty() {
+                continue;
+            }
+
+            let line = format!("{} {}", phrase.join(" "), rest);
+            let Ok(command) = parse_command(line.clone(), now, tz, order) else {
+                continue;
+            };
+            let event_text = match &command {
+                Command::OneTimeEvent(ev) => &ev.event_text,
+                Command::RepetitiveEvent(ev) => &ev.event_text,
+            };
+            // the grammar has to take the whole phrase, not a part of it
+            if *event_text == rest {
+                best = Some((length, line, command));
+            }
+        }
+    }
+    best.map(|(_, line, command)| (line, command))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Etc;
+
+    const MSK: Tz = Etc::GMTMinus3;
+
+    #[test]
+    fn extract_time_phrases() {
+        // Sunday 18 Oct 2026, 12:00 in Moscow
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 9, 0, 0).unwrap();
+        let msk = |d, h: u32, min| Utc.with_ymd_and_hms(2026, 10, d, h - 3, min, 0).unwrap();
+        let expect = [
+            (
+                "call mom in 2 hours",
+                "2 hours call mom",
+                msk(18, 14, 0),
+                "call mom",
+            ),
+            (
+                "встреча завтра в 15:00",
+                "завтра в 15:00 встреча",
+                msk(19, 15, 0),
+                "встреча",
+            ),
+            (
+                "dentist on friday at 10.30 don't forget",
+                "on friday at 10.30 dentist don't forget",
+                msk(23, 10, 30),
+                "dentist don't forget",
+            ),
+            (
+                "позвонить в банк через 30 минут",
+                "30 минут позвонить в банк",
+                msk(18, 12, 30),
+                "позвонить в банк",
+            ),
+            (
+                "meet John at 18 near the office",
+                "at 18 meet John near the office",
+                msk(18, 18, 0),
+                "meet John near the office",
+            ),
+            (
+                "meeting in room 5 at 18",
+                "at 18 meeting in room 5",
+                msk(18, 18, 0),
+                "meeting in room 5",
+            ),
+        ];
+        for (text, line, time, event_text) in expect {
+            match extract_command(text, now, MSK, DateOrder::DayMonth) {
+                Some((res_line, Command::OneTimeEvent(res))) => {
+                    assert_eq!(res_line, line, "{text}");
+                    assert_eq!(res.event_time, time, "{text}");
+                    assert_eq!(res.event_text, event_text, "{text}");
+                }
+                res => panic!("Unexpected result for {text}: {res:?}"),
+            }
+        }
+
+        for text in ["buy milk", "buy 2 apples", "at 18", "room 101 is free"] {
+            assert!(
+                extract_command(text, now, MSK, DateOrder::DayMonth).is_none(),
+                "{text}"
+            );
+        }
+    }
+}
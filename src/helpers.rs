@@ -1,20 +1,25 @@
 use crate::command::*;
 use crate::database::DataBase;
+use crate::recurrence::format_duration;
+use crate::text_data::Lang;
+use crate::timezone::{describe_timezone, local_to_utc};
 use chrono::prelude::*;
+use chrono_tz::Tz;
 use log::debug;
 
 // TODO: make test
 pub fn format_return_message_header(
     event_time: &DateTime<Utc>,
     now: DateTime<Utc>,
-    tz: i32,
+    tz: Tz,
+    lang: Lang,
+    order: DateOrder,
 ) -> String {
-    let tz = FixedOffset::west_opt(tz * 3600).expect("wrong tz?");
     let t_event = event_time.with_timezone(&tz);
     let t_now = now.with_timezone(&tz);
 
     if t_event < t_now {
-        return String::from("Event time is in the past. Is it right?");
+        return lang.event_in_past().to_owned();
     }
 
     // days from year 1 TODO: maybe day of the year?
@@ -22,14 +27,13 @@ pub fn format_return_message_header(
     let day_now = t_now.num_days_from_ce();
     let dt = day_event - day_now;
 
+    let time = t_event.format("%H:%M").to_string();
     if dt == 0 {
-        return t_event.format("I'll remind you today at %H:%M").to_string();
+        return lang.remind_today(&time);
     } else if dt == 1 {
-        return t_event
-            .format("I'll remind you tomorrow at %H:%M")
-            .to_string();
+        return lang.remind_tomorrow(&time);
     }
-    t_event.format("I'll remind you %B %e at %H:%M").to_string()
+    lang.remind_on(t_event.date_naive(), order, &time)
 }
 
 pub fn process_text_command(
@@ -37,25 +41,63 @@ pub fn process_text_command(
     text_message: &str,
     now: DateTime<Utc>,
     db: &mut DataBase,
-) -> Option<String> {
+) -> Result<String, ParseError> {
     let tz = db.get_user_timezone(uid);
-    match parse_command(String::from(text_message), now, tz)? {
-        Command::OneTimeEvent(ev) => Some(process_one_time_event_command(uid, ev, now, db)),
-        Command::RepetitiveEvent(ev) => Some(process_repetitive_event_command(uid, ev, now, db)),
+    let order = db.get_user_date_order(uid);
+    let (persistent, text_message) = split_persistent(text_message);
+    let command = parse_command(String::from(text_message), now, tz, order)?;
+    Ok(process_command(uid, command, persistent, now, db))
+}
+
+// stands for the event text when a time spec is written alone, it is never
+// read as a zone or a lead alert
+const TIME_ONLY_TEXT: &str = "_";
+
+/// Time spec written without a text, `30m`, `at 18`, `rep 9 1d`. `NoMatch`
+/// when there is anything but the time spec.
+pub fn parse_time_only(
+    input: &str,
+    now: DateTime<Utc>,
+    tz: Tz,
+    order: DateOrder,
+) -> Result<Command, ParseError> {
+    let command = parse_command(format!("{} {TIME_ONLY_TEXT}", input.trim()), now, tz, order)?;
+    let event_text = match &command {
+        Command::OneTimeEvent(ev) => &ev.event_text,
+        Command::RepetitiveEvent(ev) => &ev.event_text,
+    };
+    if event_text != TIME_ONLY_TEXT {
+        return Err(ParseError::NoMatch);
+    }
+    Ok(command)
+}
+
+/// `!at 9 pills`: the reminder is sent again until marked as done.
+fn split_persistent(text_message: &str) -> (bool, &str) {
+    match text_message.trim().strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, text_message),
     }
 }
 
-fn process_one_time_event_command(
+/// Stores an already parsed command, returns the confirmation for the user.
+pub fn process_command(
     uid: i64,
-    c: OneTimeEventImpl,
+    command: Command,
+    persistent: bool,
     now: DateTime<Utc>,
     db: &mut DataBase,
 ) -> String {
-    let tz = db.get_user_timezone(uid);
-    let mut return_string = format_return_message_header(&c.event_time, now, tz);
-    return_string.push('\n');
-    return_string.push_str(&c.event_text);
-    db.put(uid, Command::OneTimeEvent(c), now);
+    let mut return_string = format_confirmation(uid, &command, now, db);
+    if persistent {
+        let lang = db.get_user_language(uid);
+        let nag = db.get_user_nag(uid);
+        return_string.push('\n');
+        return_string.push_str(&lang.nag_note(nag.interval_minutes, nag.max_repeats));
+        db.put_persistent(uid, command, now);
+    } else {
+        db.put(uid, command, now);
+    }
 
     // delete newline char to write to log
     let tmp_string = str::replace(&return_string[..], "\n", " ");
@@ -67,59 +109,404 @@ fn process_one_time_event_command(
     return_string
 }
 
-fn process_repetitive_event_command(
+/// Changes the pending event in row `id` in place. A whole command replaces
+/// its time, text and rule, a time alone keeps the text, a text alone keeps
+/// the time. A time alone moves a series and keeps its rule, only a whole
+/// one-time command turns it into a single reminder. `None` when the event
+/// has fired or was deleted meanwhile.
+pub fn process_edit_command(
+    uid: i64,
+    id: i64,
+    text_message: &str,
+    now: DateTime<Utc>,
+    db: &mut DataBase,
+) -> Result<Option<String>, ParseError> {
+    let Some((event, parent_id)) = db.get_pending_event(id, uid) else {
+        return Ok(None);
+    };
+    let tz = db.get_user_timezone(uid);
+    let order = db.get_user_date_order(uid);
+    // the event keeps nagging or not as it was set up
+    let (_, text_message) = split_persistent(text_message);
+    let text_message = text_message.trim();
+    let command = match parse_command(text_message.to_owned(), now, tz, order) {
+        Ok(command) => command,
+        Err(ParseError::MissingText) => match parse_time_only(text_message, now, tz, order)? {
+            Command::OneTimeEvent(ev) if parent_id != -1 => {
+                let Some(series) = db.get_rep_event(parent_id, uid) else {
+                    return Ok(None);
+                };
+                let series = RepetitiveEventImpl {
+                    event_start_time: ev.event_time,
+                    ..series
+                };
+                if !db.update_repetitive_event(parent_id, uid, &series, now) {
+                    return Ok(None);
+                }
+                let command = Command::RepetitiveEvent(series);
+                return Ok(Some(format_confirmation(uid, &command, now, db)));
+            }
+            Command::OneTimeEvent(ev) => Command::OneTimeEvent(OneTimeEventImpl {
+                event_text: event.event_text,
+                ..ev
+            }),
+            Command::RepetitiveEvent(ev) => Command::RepetitiveEvent(RepetitiveEventImpl {
+                event_text: event.event_text,
+                ..ev
+            }),
+        },
+        Err(ParseError::NoMatch) => {
+            if !db.update_event_text(id, uid, text_message) {
+                return Ok(None);
+            }
+            let command = Command::OneTimeEvent(OneTimeEventImpl {
+                event_text: text_message.to_owned(),
+                ..event
+            });
+            return Ok(Some(format_confirmation(uid, &command, now, db)));
+        }
+        Err(e) => return Err(e),
+    };
+
+    let updated = match &command {
+        Command::OneTimeEvent(ev) if parent_id == -1 => db.update_one_time_event(id, uid, ev, now),
+        Command::RepetitiveEvent(ev) if parent_id != -1 => {
+            db.update_repetitive_event(parent_id, uid, ev, now)
+        }
+        // a one-time event becomes a series or the other way round
+        _ => {
+            let persistent = db.is_pending_event_persistent(id, uid);
+            let deleted = if parent_id == -1 {
+                db.delete_pending_event(id, uid)
+            } else {
+                db.end_rep_event(parent_id, uid)
+            };
+            deleted
+                && if persistent {
+                    db.put_persistent(uid, command.clone(), now)
+                } else {
+                    db.put(uid, command.clone(), now)
+                }
+        }
+    };
+    if !updated {
+        return Ok(None);
+    }
+    Ok(Some(format_confirmation(uid, &command, now, db)))
+}
+
+/// Every non-empty line is a separate command, the reply sums up all of them.
+/// `NoMatch` when no line looks like a time spec, i.e. it's just a long text.
+pub fn process_multiline_command(
+    uid: i64,
+    text_message: &str,
+    now: DateTime<Utc>,
+    db: &mut DataBase,
+) -> Result<String, ParseError> {
+    let lines: Vec<&str> = text_message
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let tz = db.get_user_timezone(uid);
+    let lang = db.get_user_language(uid);
+    let order = db.get_user_date_order(uid);
+    if lines.iter().all(|line| {
+        let (_, line) = split_persistent(line);
+        parse_command(line.to_string(), now, tz, order) == Err(ParseError::NoMatch)
+    }) {
+        return Err(ParseError::NoMatch);
+    }
+
+    let mut scheduled = 0;
+    let mut report = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        match process_text_command(uid, line, now, db) {
+            Ok(ret_text) => {
+                scheduled += 1;
+                report.push(format!("{}) {}", i + 1, ret_text));
+            }
+            Err(e) => report.push(format!(
+                "{}) {}",
+                i + 1,
+                lang.skipped(line, &lang.parse_error(&e))
+            )),
+        }
+    }
+
+    Ok(format!(
+        "{}\n\n{}",
+        lang.scheduled_summary(scheduled, lines.len()),
+        report.join("\n\n")
+    ))
+}
+
+/// When the command fires and what it says, without storing it.
+pub fn format_confirmation(
     uid: i64,
-    c: RepetitiveEventImpl,
+    command: &Command,
     now: DateTime<Utc>,
     db: &mut DataBase,
 ) -> String {
     let tz = db.get_user_timezone(uid);
-    let mut return_string = format_return_message_header(&c.event_start_time, now, tz);
-    return_string.push('\n');
-    return_string.push_str(&c.event_text);
-    db.put(uid, Command::RepetitiveEvent(c), now);
+    let lang = db.get_user_language(uid);
+    let order = db.get_user_date_order(uid);
+    match command {
+        Command::OneTimeEvent(c) => {
+            let mut return_string =
+                format_return_message_header(&c.event_time, now, tz, lang, order);
+            if let Some(zones) = format_zone_times(c.event_time, c.event_timezone, tz, lang, order)
+            {
+                return_string.push('\n');
+                return_string.push_str(&zones);
+            }
+            return_string.push('\n');
+            return_string.push_str(&c.event_text);
+            // alerts that would be in the past are not stored
+            let (leads, late): (Vec<chrono::Duration>, Vec<chrono::Duration>) = c
+                .event_leads
+                .iter()
+                .partition(|lead| c.event_time - **lead > now);
+            if !leads.is_empty() {
+                return_string.push('\n');
+                return_string.push_str(&lang.leads(&format_leads(&leads, lang)));
+            }
+            if !late.is_empty() {
+                return_string.push('\n');
+                return_string.push_str(&lang.leads_too_late(&format_leads(&late, lang)));
+            }
+            return_string
+        }
+        Command::RepetitiveEvent(c) => {
+            let rule_tz = c.event_timezone.unwrap_or(tz);
+            let first_time = c
+                .event_rule
+                .next_occurrence(c.event_start_time, &rule_tz, now);
+            let mut return_string = format_return_message_header(&first_time, now, tz, lang, order);
+            if let Some(zones) = format_zone_times(first_time, c.event_timezone, tz, lang, order) {
+                return_string.push('\n');
+                return_string.push_str(&zones);
+            }
+            return_string.push('\n');
+            return_string.push_str(&c.event_text);
+            return_string.push('\n');
+            return_string.push_str(&lang.repeats(&c.event_rule.describe(lang)));
+            if let Some(end) = format_series_end(c, rule_tz, lang, order) {
+                return_string.push('\n');
+                return_string.push_str(&lang.ends(&end));
+            }
+            if !c.event_leads.is_empty() {
+                return_string.push('\n');
+                return_string.push_str(&lang.leads(&format_leads(&c.event_leads, lang)));
+            }
+            return_string
+        }
+    }
+}
 
-    // delete newline char to write to log
-    let tmp_string = str::replace(&return_string[..], "\n", " ");
-    debug!(
-        "Successfully process command, return string - <{}>",
-        tmp_string
-    );
+// dry run listing of a repetitive event
+const PREVIEW_OCCURRENCES: usize = 5;
 
-    return_string
+/// Explains how `/parse` reads the line, nothing is stored.
+pub fn format_parse_preview(
+    uid: i64,
+    text_message: &str,
+    now: DateTime<Utc>,
+    db: &mut DataBase,
+) -> Result<String, ParseError> {
+    let tz = db.get_user_timezone(uid);
+    let lang = db.get_user_language(uid);
+    let order = db.get_user_date_order(uid);
+    let (persistent, text_message) = split_persistent(text_message);
+    let command = parse_command(String::from(text_message), now, tz, order)?;
+
+    // the zone written in the command first, then the user's one
+    let local = |time: DateTime<Utc>, zone: Option<Tz>| {
+        let mut zones = vec![tz];
+        if let Some(zone) = zone.filter(|zone| *zone != tz) {
+            zones.insert(0, zone);
+        }
+        zones
+            .iter()
+            .map(|tz| {
+                format!(
+                    "{} ({})",
+                    time.with_timezone(tz).format("%Y-%m-%d %H:%M"),
+                    describe_timezone(tz, time)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(" = ")
+    };
+    let leads = match &command {
+        Command::OneTimeEvent(c) => format_leads(&c.event_leads, lang),
+        Command::RepetitiveEvent(c) => format_leads(&c.event_leads, lang),
+    };
+    let mut preview = match command {
+        Command::OneTimeEvent(c) => lang.parse_preview(
+            &c.event_text,
+            &local(c.event_time, c.event_timezone),
+            &c.event_time.format("%Y-%m-%d %H:%M").to_string(),
+            None,
+            &[],
+        ),
+        Command::RepetitiveEvent(c) => {
+            let rule_tz = c.event_timezone.unwrap_or(tz);
+            let next = c.next_occurrences(&rule_tz, now, PREVIEW_OCCURRENCES);
+            let first = next.first().copied().unwrap_or_else(|| {
+                c.event_rule
+                    .next_occurrence(c.event_start_time, &rule_tz, now)
+            });
+            let end = format_series_end(&c, rule_tz, lang, order)
+                .map(|end| lang.list_ends(&end))
+                .unwrap_or_default();
+            let rule = c.event_rule.describe(lang) + &end;
+            let next: Vec<String> = next
+                .into_iter()
+                .map(|time| local(time, c.event_timezone))
+                .collect();
+            lang.parse_preview(
+                &c.event_text,
+                &local(first, c.event_timezone),
+                &first.format("%Y-%m-%d %H:%M").to_string(),
+                Some(&rule),
+                &next,
+            )
+        }
+    };
+    if !leads.is_empty() {
+        preview.push('\n');
+        preview.push_str(&lang.leads(&leads));
+    }
+    if persistent {
+        let nag = db.get_user_nag(uid);
+        return Ok(format!(
+            "{preview}\n{}",
+            lang.nag_note(nag.interval_minutes, nag.max_repeats)
+        ));
+    }
+    Ok(preview)
 }
 
-pub fn get_active_event_list(uid: i64, db: &mut DataBase) -> Vec<String> {
+/// Reminders due from `now` till the end of the user's day, a series is
+/// shown once at its nearest occurrence.
+pub fn format_digest(uid: i64, now: DateTime<Utc>, db: &mut DataBase) -> String {
+    let tz = db.get_user_timezone(uid);
+    let lang = db.get_user_language(uid);
+    let tomorrow = now.with_timezone(&tz).date_naive() + chrono::Duration::days(1);
+    let day_end = local_to_utc(&tz, tomorrow.and_time(NaiveTime::MIN));
+
+    let mut items: Vec<(DateTime<Utc>, String)> = db
+        .get_one_time_events_between(uid, now, day_end)
+        .into_iter()
+        .map(|ev| (ev.event_time, ev.event_text))
+        .collect();
+    for (command, _) in db.get_all_rep_events(uid) {
+        let Command::RepetitiveEvent(ev) = command else {
+            continue;
+        };
+        let rule_tz = ev.event_timezone.unwrap_or(tz);
+        let times: Vec<DateTime<Utc>> = ev
+            .occurrences_from(&rule_tz, now)
+            .take_while(|time| *time < day_end)
+            .collect();
+        match times[..] {
+            [] => {}
+            [time] => items.push((time, ev.event_text)),
+            [time, ..] => items.push((time, lang.digest_repeats(&ev.event_text, times.len()))),
+        }
+    }
+    if items.is_empty() {
+        return lang.digest_empty().to_owned();
+    }
+
+    items.sort_by_key(|(time, _)| *time);
+    let lines: Vec<String> = items
+        .iter()
+        .map(|(time, text)| format!("{} {}", time.with_timezone(&tz).format("%H:%M"), text))
+        .collect();
+    lang.digest(&lines.join("\n"))
+}
+
+pub fn get_active_event_list(uid: i64, db: &mut DataBase) -> (Vec<String>, Vec<i64>) {
     let mut result = Vec::new();
+    let mut result_id = Vec::new();
     let command_vector = db.get_all_active_events(uid);
-    let tz = db.get_user_timezone(uid) as i64;
-    let dt = chrono::Duration::seconds(-tz * 60 * 60);
-    for command in command_vector {
+    let tz = db.get_user_timezone(uid);
+    let lang = db.get_user_language(uid);
+    let order = db.get_user_date_order(uid);
+    for (command, rule, id) in command_vector {
         match command {
             Command::OneTimeEvent(c) => {
                 let text: String = c.event_text.chars().take(40).collect();
-                let date: String = (c.event_time + dt).format("%e %b %k.%M").to_string();
-                result.push(format!("{} : _{}_", text, date));
+                let date =
+                    lang.short_datetime(c.event_time.with_timezone(&tz).naive_local(), order);
+                let leads = if c.event_leads.is_empty() {
+                    String::new()
+                } else {
+                    lang.list_leads(&format_leads(&c.event_leads, lang))
+                };
+                match rule {
+                    Some(rule) => result.push(format!(
+                        "{} : _{}, {}{}_",
+                        text,
+                        date,
+                        rule.describe(lang),
+                        leads
+                    )),
+                    None => result.push(format!("{} : _{}{}_", text, date, leads)),
+                }
+                result_id.push(id);
             }
             Command::RepetitiveEvent(_ev) => {}
         }
     }
-    result
+    (result, result_id)
+}
+
+pub fn format_active_event_list(uid: i64, db: &mut DataBase) -> String {
+    let (list, _) = get_active_event_list(uid, db);
+    if list.is_empty() {
+        return db.get_user_language(uid).no_active_events().to_owned();
+    }
+    list.iter()
+        .enumerate()
+        .fold(String::from(""), |s, (i, val)| {
+            s + &format!("{}) {}\n", i + 1, val)
+        })
 }
 
 pub fn get_rep_event_list(uid: i64, db: &mut DataBase) -> (Vec<String>, Vec<i64>) {
     let mut result_str = Vec::new();
     let mut result_id = Vec::new();
     let command_vector = db.get_all_rep_events(uid);
-    // const DEFAULT_TZ: i64 = 3;
-    // let dt = chrono::Duration::seconds(DEFAULT_TZ * 60 * 60);
+    let tz = db.get_user_timezone(uid);
+    let lang = db.get_user_language(uid);
+    let order = db.get_user_date_order(uid);
     for line in command_vector {
         let id: i64 = line.1;
         let command = line.0;
         match command {
             Command::RepetitiveEvent(ev) => {
                 let text: String = ev.event_text.chars().take(40).collect();
-                result_str.push(text);
+                let start = lang
+                    .short_datetime(ev.event_start_time.with_timezone(&tz).naive_local(), order);
+                let end = format_series_end(&ev, ev.event_timezone.unwrap_or(tz), lang, order)
+                    .map(|end| lang.list_ends(&end))
+                    .unwrap_or_default();
+                let leads = if ev.event_leads.is_empty() {
+                    String::new()
+                } else {
+                    lang.list_leads(&format_leads(&ev.event_leads, lang))
+                };
+                result_str.push(format!(
+                    "{} : _{}, {}{}{}_",
+                    text,
+                    ev.event_rule.describe(lang),
+                    lang.since(&start),
+                    end,
+                    leads
+                ));
                 result_id.push(id);
             }
             Command::OneTimeEvent(_ev) => {}
@@ -127,3 +514,282 @@ pub fn get_rep_event_list(uid: i64, db: &mut DataBase) -> (Vec<String>, Vec<i64>
     }
     (result_str, result_id)
 }
+
+/// The time in the zone written in the command next to the user's one,
+/// `None` when there is no such zone or it is the user's zone anyway.
+fn format_zone_times(
+    time: DateTime<Utc>,
+    zone: Option<Tz>,
+    tz: Tz,
+    lang: Lang,
+    order: DateOrder,
+) -> Option<String> {
+    let zone = zone.filter(|zone| *zone != tz)?;
+    let local = |tz: &Tz| lang.short_datetime(time.with_timezone(tz).naive_local(), order);
+    Some(lang.zone_times(
+        &local(&zone),
+        &describe_timezone(&zone, time),
+        &local(&tz),
+        &describe_timezone(&tz, time),
+    ))
+}
+
+/// `1h, 15m`, empty when there are no lead alerts.
+fn format_leads(leads: &[chrono::Duration], lang: Lang) -> String {
+    let leads: Vec<String> = leads.iter().map(|l| format_duration(*l, lang)).collect();
+    leads.join(", ")
+}
+
+/// `after 3 reminders or on 31 Dec 2026`, `None` for endless series.
+fn format_series_end(
+    ev: &RepetitiveEventImpl,
+    tz: Tz,
+    lang: Lang,
+    order: DateOrder,
+) -> Option<String> {
+    let mut parts = Vec::new();
+    if let Some(count) = ev.event_count {
+        parts.push(lang.ends_after(count));
+    }
+    if let Some(until) = ev.event_until {
+        // the bound is exclusive, show the last day of the series
+        let last_day = (until - chrono::Duration::seconds(1)).with_timezone(&tz);
+        parts.push(lang.ends_on(last_day.date_naive(), order));
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    Some(parts.join(lang.or()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::tests::{db_with_user, utc};
+    use crate::recurrence::Recurrence;
+
+    #[test]
+    fn multiline_schedules_every_line() {
+        let mut db = db_with_user();
+        let now = utc(1, 8, 0);
+        let lang = Lang::En;
+
+        let reply = process_multiline_command(1, "at 10 call\n\n  2h tea\n", now, &mut db).unwrap();
+        assert!(reply.starts_with(&lang.scheduled_summary(2, 2)), "{reply}");
+        assert_eq!(db.get_all_active_events(1).len(), 2);
+
+        // wrong lines are explained, the rest is scheduled
+        let reply =
+            process_multiline_command(1, "at 12 lunch\nat 25 nap\nbuy milk", now, &mut db).unwrap();
+        assert!(reply.starts_with(&lang.scheduled_summary(1, 3)), "{reply}");
+        let hour_error = ParseError::HourOutOfRange {
+            hour: 25,
+            am_pm: false,
+        };
+        assert!(reply.contains(&format!(
+            "2) {}",
+            lang.skipped("at 25 nap", &lang.parse_error(&hour_error))
+        )));
+        assert!(reply.contains(&format!(
+            "3) {}",
+            lang.skipped("buy milk", &lang.parse_error(&ParseError::NoMatch))
+        )));
+        assert_eq!(db.get_all_active_events(1).len(), 3);
+
+        // just a long text, nothing is scheduled
+        assert_eq!(
+            process_multiline_command(1, "buy milk\nand bread", now, &mut db),
+            Err(ParseError::NoMatch)
+        );
+        assert_eq!(db.get_all_active_events(1).len(), 3);
+    }
+
+    #[test]
+    fn digest_lists_the_rest_of_the_day() {
+        let mut db = db_with_user();
+        // 7.00 in Berlin
+        let now = utc(5, 6, 0);
+        let lang = Lang::En;
+        assert_eq!(format_digest(1, now, &mut db), lang.digest_empty());
+
+        let one_time = |text: &str, event_time| {
+            Command::OneTimeEvent(OneTimeEventImpl {
+                event_text: text.to_owned(),
+                event_time,
+                event_timezone: None,
+                event_leads: Vec::new(),
+            })
+        };
+        let series = |text: &str, start, period| {
+            Command::RepetitiveEvent(RepetitiveEventImpl {
+                event_text: text.to_owned(),
+                event_start_time: start,
+                event_rule: Recurrence::Interval(period),
+                event_until: None,
+                event_count: None,
+                event_timezone: None,
+                event_leads: Vec::new(),
+            })
+        };
+        db.put(1, one_time("dinner", utc(5, 20, 0)), now);
+        // still the 5th in UTC, but the 6th in Berlin
+        db.put(1, one_time("late", utc(5, 23, 30)), now);
+        db.put(
+            1,
+            series("standup", utc(5, 9, 0), chrono::Duration::days(1)),
+            now,
+        );
+        // every 5 minutes from 9.00 to midnight
+        db.put(
+            1,
+            series("water", utc(5, 8, 0), chrono::Duration::minutes(5)),
+            now,
+        );
+
+        let expected = [
+            format!("09:00 {}", lang.digest_repeats("water", 180)),
+            "10:00 standup".to_owned(),
+            "21:00 dinner".to_owned(),
+        ];
+        assert_eq!(
+            format_digest(1, now, &mut db),
+            lang.digest(&expected.join("\n"))
+        );
+    }
+
+    #[test]
+    fn edit_keeps_what_is_not_written() {
+        let mut db = db_with_user();
+        // 9.00 in Berlin
+        let now = utc(5, 8, 0);
+        let edit = |input: &str, db: &mut DataBase| {
+            let id = db.get_all_active_events(1)[0].2;
+            assert!(
+                process_edit_command(1, id, input, now, db)
+                    .unwrap()
+                    .is_some(),
+                "{input}"
+            );
+            db.get_all_active_events(1)
+                .into_iter()
+                .map(|ev| ev.0)
+                .collect::<Vec<_>>()
+        };
+        let one_time = |text: &str, event_time| {
+            Command::OneTimeEvent(OneTimeEventImpl {
+                event_text: text.to_owned(),
+                event_time,
+                event_timezone: None,
+                event_leads: Vec::new(),
+            })
+        };
+
+        // the stored text is neither a zone nor a lead alert, `!` is dropped
+        db.put(1, one_time("UTC sync -5m", utc(5, 10, 0)), now);
+        assert_eq!(
+            edit("!at 18", &mut db),
+            vec![one_time("UTC sync -5m", utc(5, 17, 0))]
+        );
+        assert_eq!(edit("sync", &mut db), vec![one_time("sync", utc(5, 17, 0))]);
+
+        // a time alone moves a series, rule, end and alerts stay
+        let standup = RepetitiveEventImpl {
+            event_text: String::from("standup"),
+            event_start_time: utc(6, 9, 0),
+            event_rule: Recurrence::Interval(chrono::Duration::days(1)),
+            event_until: None,
+            event_count: Some(5),
+            event_timezone: Some(chrono_tz::Asia::Tokyo),
+            event_leads: vec![chrono::Duration::minutes(10)],
+        };
+        db.delete_pending_event(db.get_all_active_events(1)[0].2, 1);
+        db.put(1, Command::RepetitiveEvent(standup.clone()), now);
+        edit("at 18", &mut db);
+        assert_eq!(
+            db.get_all_rep_events(1)
+                .into_iter()
+                .map(|r| r.0)
+                .collect::<Vec<_>>(),
+            vec![Command::RepetitiveEvent(RepetitiveEventImpl {
+                event_start_time: utc(5, 17, 0),
+                ..standup
+            })]
+        );
+
+        // a whole one-time command makes it a single reminder and back
+        assert_eq!(
+            edit("at 20 retro", &mut db),
+            vec![one_time("retro", utc(5, 19, 0))]
+        );
+        assert!(db.get_all_rep_events(1).is_empty());
+        edit("rep 9 1d", &mut db);
+        match &db.get_all_rep_events(1)[..] {
+            [(Command::RepetitiveEvent(ev), _)] => {
+                assert_eq!(ev.event_text, "retro");
+                assert_eq!(
+                    ev.event_rule,
+                    Recurrence::Interval(chrono::Duration::days(1))
+                );
+            }
+            res => panic!("Unexpected result: {res:?}"),
+        }
+    }
+
+    #[test]
+    fn confirmation_shows_stored_leads() {
+        let mut db = db_with_user();
+        // 9.00 in Berlin
+        let now = utc(5, 8, 0);
+        let lang = Lang::En;
+
+        let reply = process_text_command(1, "at 18 -2d -1h party", now, &mut db).unwrap();
+        assert!(reply.ends_with(&format!(
+            "party\n{}\n{}",
+            lang.leads("1h"),
+            lang.leads_too_late("2d")
+        )));
+        // only the hour before is stored
+        assert_eq!(
+            db.get_all_active_events(1)[0].0,
+            Command::OneTimeEvent(OneTimeEventImpl {
+                event_text: String::from("party"),
+                event_time: utc(5, 17, 0),
+                event_timezone: None,
+                event_leads: vec![chrono::Duration::hours(1)],
+            })
+        );
+    }
+
+    #[test]
+    fn edit_series_to_one_time_keeps_fired_and_nag() {
+        let mut db = db_with_user();
+        let pills = RepetitiveEventImpl {
+            event_text: String::from("pills"),
+            event_start_time: utc(5, 8, 0),
+            event_rule: Recurrence::Interval(chrono::Duration::days(1)),
+            event_until: None,
+            event_count: None,
+            event_timezone: None,
+            event_leads: Vec::new(),
+        };
+        db.put_persistent(1, Command::RepetitiveEvent(pills), utc(5, 7, 0));
+        let fired = db.extract_events_happens_already(utc(5, 8, 0))[0].id;
+        let now = utc(5, 8, 5);
+
+        let id = db.get_all_active_events(1)[0].2;
+        let reply = process_edit_command(1, id, "at 20 vitamins", now, &mut db).unwrap();
+        assert!(reply.is_some());
+        assert!(db.get_all_rep_events(1).is_empty());
+        // today's pills still wait for a button
+        assert!(db.get_fired_event(fired, 1).is_some());
+        db.delete_fired_event(fired, 1);
+
+        // the reminder is still sent again until done
+        assert_eq!(db.extract_events_happens_already(utc(5, 19, 0)).len(), 1);
+        let interval = db.get_user_nag(1).interval_minutes as i64;
+        assert_eq!(
+            db.get_nearest_wakeup(),
+            Some(utc(5, 19, 0) + chrono::Duration::minutes(interval))
+        );
+    }
+}
@@ -1,23 +1,28 @@
 use calendarize::calendarize_with_offset;
 use chrono::NaiveDate;
+use chrono_tz::Tz;
 use frankenstein::types::{InlineKeyboardButton, InlineKeyboardMarkup};
 
+use crate::command::DATE_ORDERS;
+use crate::state::SNOOZE_BUTTONS;
+use crate::text_data::{LANGUAGES, Lang};
+
 #[rustfmt::skip]
-pub(crate) fn make_main_action_keyboard() -> InlineKeyboardMarkup {
+pub(crate) fn make_main_action_keyboard(lang: Lang) -> InlineKeyboardMarkup {
     let keyboard: Vec<Vec<InlineKeyboardButton>> = vec![
         vec![
-            InlineKeyboardButton::builder().text("at").callback_data("at").build(),
-            InlineKeyboardButton::builder().text("after").callback_data("after").build(),
+            InlineKeyboardButton::builder().text(lang.button_at()).callback_data("at").build(),
+            InlineKeyboardButton::builder().text(lang.button_after()).callback_data("after").build(),
         ],
         vec![
-            InlineKeyboardButton::builder().text("5m").callback_data("5m").build(),
-            InlineKeyboardButton::builder().text("30m").callback_data("30m").build(),
-            InlineKeyboardButton::builder().text("1h").callback_data("1h").build(),
+            InlineKeyboardButton::builder().text(lang.button_duration("5m")).callback_data("5m").build(),
+            InlineKeyboardButton::builder().text(lang.button_duration("30m")).callback_data("30m").build(),
+            InlineKeyboardButton::builder().text(lang.button_duration("1h")).callback_data("1h").build(),
         ],
         vec![
-            InlineKeyboardButton::builder().text("3h").callback_data("3h").build(),
-            InlineKeyboardButton::builder().text("1d").callback_data("1d").build(),
-            InlineKeyboardButton::builder().text("Ok").callback_data("Ok").build(),
+            InlineKeyboardButton::builder().text(lang.button_duration("3h")).callback_data("3h").build(),
+            InlineKeyboardButton::builder().text(lang.button_duration("1d")).callback_data("1d").build(),
+            InlineKeyboardButton::builder().text(lang.button_ok()).callback_data("Ok").build(),
         ],
     ];
 
@@ -26,21 +31,159 @@ pub(crate) fn make_main_action_keyboard() -> InlineKeyboardMarkup {
     }
 }
 
-pub fn make_calendar_keyboard(year: i32, month: u32) -> InlineKeyboardMarkup {
+const TIMEZONE_BUTTONS: [[&str; 3]; 3] = [
+    ["Europe/London", "Europe/Berlin", "Europe/Moscow"],
+    ["Asia/Dubai", "Asia/Kolkata", "Asia/Tokyo"],
+    ["America/New_York", "America/Los_Angeles", "UTC"],
+];
+
+pub(crate) fn make_timezone_keyboard(lang: Lang) -> InlineKeyboardMarkup {
+    let keyboard: Vec<Vec<InlineKeyboardButton>> = TIMEZONE_BUTTONS
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|name| {
+                    InlineKeyboardButton::builder()
+                        .text(lang.city(name))
+                        .callback_data(format!("tz:{name}"))
+                        .build()
+                })
+                .collect()
+        })
+        .collect();
+
+    InlineKeyboardMarkup {
+        inline_keyboard: keyboard,
+    }
+}
+
+pub(crate) fn make_language_keyboard() -> InlineKeyboardMarkup {
+    let keyboard: Vec<Vec<InlineKeyboardButton>> = vec![
+        LANGUAGES
+            .iter()
+            .map(|lang| {
+                InlineKeyboardButton::builder()
+                    .text(lang.name())
+                    .callback_data(format!("lang:{}", lang.code()))
+                    .build()
+            })
+            .collect(),
+    ];
+
+    InlineKeyboardMarkup {
+        inline_keyboard: keyboard,
+    }
+}
+
+pub(crate) fn make_date_order_keyboard(lang: Lang) -> InlineKeyboardMarkup {
+    let keyboard: Vec<Vec<InlineKeyboardButton>> = vec![
+        DATE_ORDERS
+            .iter()
+            .map(|order| {
+                InlineKeyboardButton::builder()
+                    .text(lang.date_order_example(*order))
+                    .callback_data(format!("date_order:{}", order.code()))
+                    .build()
+            })
+            .collect(),
+    ];
+
+    InlineKeyboardMarkup {
+        inline_keyboard: keyboard,
+    }
+}
+
+pub(crate) fn make_timezone_confirm_keyboard(tz: &Tz, lang: Lang) -> InlineKeyboardMarkup {
+    let keyboard: Vec<Vec<InlineKeyboardButton>> = vec![vec![
+        InlineKeyboardButton::builder()
+            .text(lang.button_yes())
+            .callback_data(format!("tz:{}", tz.name()))
+            .build(),
+        InlineKeyboardButton::builder()
+            .text(lang.button_no())
+            .callback_data("ignore")
+            .build(),
+    ]];
+
+    InlineKeyboardMarkup {
+        inline_keyboard: keyboard,
+    }
+}
+
+pub(crate) fn make_fuzzy_keyboard(lang: Lang) -> InlineKeyboardMarkup {
+    let keyboard: Vec<Vec<InlineKeyboardButton>> = vec![vec![
+        InlineKeyboardButton::builder()
+            .text(lang.button_confirm())
+            .callback_data("fuzzy:confirm")
+            .build(),
+        InlineKeyboardButton::builder()
+            .text(lang.button_edit())
+            .callback_data("fuzzy:edit")
+            .build(),
+    ]];
+
+    InlineKeyboardMarkup {
+        inline_keyboard: keyboard,
+    }
+}
+
+#[rustfmt::skip]
+pub(crate) fn make_fired_keyboard(id: i64, lang: Lang) -> InlineKeyboardMarkup {
+    let button = |text: &str, action: &str| {
+        InlineKeyboardButton::builder().text(text).callback_data(format!("fired:{id}:{action}")).build()
+    };
+    let mut first_row = vec![button(lang.button_done(), "done")];
+    for (name, _) in SNOOZE_BUTTONS {
+        first_row.push(button(&format!("+{}", lang.button_duration(name)), name));
+    }
+    let keyboard: Vec<Vec<InlineKeyboardButton>> = vec![
+        first_row,
+        vec![
+            button(lang.button_tomorrow(), "tomorrow"),
+            button(lang.button_later(), "custom"),
+            button(lang.button_nag(), "nag"),
+        ],
+    ];
+
+    InlineKeyboardMarkup {
+        inline_keyboard: keyboard,
+    }
+}
+
+// edit buttons in a row under a listing
+const EDIT_BUTTONS_IN_ROW: usize = 5;
+
+/// Numbered buttons matching the lines of a listing, `edit:<id>`.
+pub(crate) fn make_edit_keyboard(ids: &[i64]) -> InlineKeyboardMarkup {
+    let buttons: Vec<InlineKeyboardButton> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| {
+            InlineKeyboardButton::builder()
+                .text(format!("✏️ {}", i + 1))
+                .callback_data(format!("edit:{id}"))
+                .build()
+        })
+        .collect();
+
+    InlineKeyboardMarkup {
+        inline_keyboard: buttons
+            .chunks(EDIT_BUTTONS_IN_ROW)
+            .map(|row| row.to_vec())
+            .collect(),
+    }
+}
+
+pub fn make_calendar_keyboard(year: i32, month: u32, lang: Lang) -> InlineKeyboardMarkup {
     let mut rows: Vec<Vec<InlineKeyboardButton>> = Vec::new();
 
-    let month_name = NaiveDate::from_ymd_opt(year, month, 1)
-        .expect("valid date")
-        .format("%B")
-        .to_string(); // e.g. "October"
     let header = InlineKeyboardButton::builder()
-        .text(format!("{} {}", month_name, year))
+        .text(format!("{} {}", lang.month_name(month), year))
         .callback_data("ignore")
         .build();
     rows.push(vec![header]);
 
-    let month_matrix =
-        calendarize_with_offset(chrono::NaiveDate::from_ymd_opt(year, month, 1).unwrap(), 1);
+    let month_matrix = calendarize_with_offset(NaiveDate::from_ymd_opt(year, month, 1).unwrap(), 1);
 
     for week in month_matrix {
         let mut buttons: Vec<InlineKeyboardButton> = Vec::new();
@@ -66,11 +209,11 @@ pub fn make_calendar_keyboard(year: i32, month: u32) -> InlineKeyboardMarkup {
             .callback_data("previous-month")
             .build(),
         InlineKeyboardButton::builder()
-            .text("today")
+            .text(lang.button_today())
             .callback_data("today")
             .build(),
         InlineKeyboardButton::builder()
-            .text("tomorrow")
+            .text(lang.button_tomorrow())
             .callback_data("tomorrow")
             .build(),
         InlineKeyboardButton::builder()
@@ -4,8 +4,8 @@ mod tests {
     use frankenstein::types::{InlineKeyboardButton, InlineKeyboardMarkup};
 
     use crate::{
-        FrontendHandler, database, engine, handle_command_to_frontend,
-        state::{EXPECT_BUTTON_PUSH, EXPECT_DURATION_MSG, FrontendCommand},
+        FrontendHandler, database, engine, handle_command_to_frontend, state::FrontendCommand,
+        text_data::Lang,
     };
 
     struct Message {
@@ -110,8 +110,16 @@ mod tests {
             let mut front = MockFront::new();
             let mut engine = engine::Engine::new(database::DbMode::InMemory);
             engine
-                .add_user(uid, "name", uid, "", "", -3)
-                .expect("cannot add user"); // TODO: chaos tz
+                .add_user(database::UserInfo {
+                    uid,
+                    name: "name",
+                    chat_id: uid,
+                    first_name: "",
+                    last_name: "",
+                    tz: chrono_tz::Europe::Moscow, // TODO: chaos tz
+                    lang: Lang::En,
+                })
+                .expect("cannot add user");
 
             let labels = &["user_write_msg", "user_push_button", "tick"];
 
@@ -124,9 +132,9 @@ mod tests {
                                 .last()
                                 .map(|last| {
                                     src.log_value("last_msg", &last.msg);
-                                    if last.msg.starts_with(EXPECT_BUTTON_PUSH) {
+                                    if last.msg == Lang::En.choose_date() {
                                         ExpectedNextAction::PushButton
-                                    } else if last.msg == EXPECT_DURATION_MSG {
+                                    } else if last.msg == Lang::En.expect_duration() {
                                         ExpectedNextAction::WriteDurationSpec
                                     } else {
                                         ExpectedNextAction::None
@@ -0,0 +1,498 @@
+use chrono::Duration;
+use chrono::prelude::*;
+use chrono_tz::Tz;
+use croner::Cron;
+use croner::parser::{CronParser, Seconds, Year};
+
+use crate::text_data::{Lang, plural_ru};
+use crate::timezone::local_to_utc;
+
+/// How a repetitive event repeats. Times of day are taken from the start
+/// time in the user's local calendar.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recurrence {
+    /// Fixed period, see `next_interval_occurrence`.
+    Interval(Duration),
+    /// Every `n` months on the day of the start date. Days missing in short
+    /// months fall on the month's last day, e.g. 31 Jan -> 28 Feb -> 31 Mar.
+    Months(u32),
+    /// Last day of every month.
+    LastDayOfMonth,
+    /// `nth` weekday of every month, `-1` means the last one. Months without
+    /// a fifth weekday are skipped.
+    WeekdayOfMonth { nth: i8, weekday: Weekday },
+    /// Chosen days of every week, sorted from Monday.
+    Weekdays(Vec<Weekday>),
+    /// Standard 5-field cron expression matched against the local time.
+    Cron(Box<Cron>),
+}
+
+impl Recurrence {
+    /// Parses a 5-field cron expression like `0 9 * * 1-5`. Expressions
+    /// that never match (e.g. `0 0 30 2 *`) are rejected.
+    pub fn cron(expression: &str) -> Option<Recurrence> {
+        let cron = CronParser::builder()
+            .seconds(Seconds::Disallowed)
+            .year(Year::Disallowed)
+            .build()
+            .parse(expression)
+            .ok()?;
+        cron.find_next_occurrence(&DateTime::UNIX_EPOCH, true)
+            .ok()?;
+        Some(Recurrence::Cron(Box::new(cron)))
+    }
+
+    /// Nearest occurrence not earlier than `now` (and `start`).
+    pub fn next_occurrence(
+        &self,
+        start: DateTime<Utc>,
+        tz: &Tz,
+        now: DateTime<Utc>,
+    ) -> DateTime<Utc> {
+        match self {
+            Recurrence::Interval(period) => next_interval_occurrence(start, *period, tz, now),
+            Recurrence::Weekdays(days) => next_weekday_occurrence(start, days, tz, now),
+            Recurrence::Cron(cron) => cron
+                .find_next_occurrence(&start.max(now).with_timezone(tz), true)
+                .expect("cron expression is checked to match on parsing")
+                .with_timezone(&Utc),
+            _ => self.next_calendar_occurrence(start, tz, now),
+        }
+    }
+
+    /// Columns `event_wait` and `event_rule` of `scheduled_event`.
+    pub fn to_db(&self) -> (Option<i64>, Option<String>) {
+        match self {
+            Recurrence::Interval(period) => (Some(period.num_seconds()), None),
+            Recurrence::Months(n) => (None, Some(format!("months:{n}"))),
+            Recurrence::LastDayOfMonth => (None, Some("last_day".to_owned())),
+            Recurrence::WeekdayOfMonth { nth, weekday } => {
+                (None, Some(format!("weekday_of_month:{nth}:{weekday}")))
+            }
+            Recurrence::Weekdays(days) => {
+                let days: Vec<String> = days.iter().map(|d| d.to_string()).collect();
+                (None, Some(format!("weekdays:{}", days.join(","))))
+            }
+            Recurrence::Cron(cron) => (None, Some(format!("cron:{}", cron.as_str()))),
+        }
+    }
+
+    pub fn from_db(wait: Option<i64>, rule: Option<String>) -> Option<Recurrence> {
+        let Some(rule) = rule else {
+            return Some(Recurrence::Interval(Duration::seconds(wait?)));
+        };
+        if let Some(expression) = rule.strip_prefix("cron:") {
+            return Recurrence::cron(expression);
+        }
+        let mut parts = rule.split(':');
+        let rule = match (parts.next()?, parts.next(), parts.next()) {
+            ("months", Some(n), None) => Recurrence::Months(n.parse().ok()?),
+            ("last_day", None, None) => Recurrence::LastDayOfMonth,
+            ("weekday_of_month", Some(nth), Some(weekday)) => Recurrence::WeekdayOfMonth {
+                nth: nth.parse().ok()?,
+                weekday: weekday.parse().ok()?,
+            },
+            ("weekdays", Some(days), None) => Recurrence::Weekdays(
+                days.split(',')
+                    .map(|d| d.parse().ok())
+                    .collect::<Option<Vec<_>>>()?,
+            ),
+            _ => return None,
+        };
+        Some(rule)
+    }
+
+    /// Human readable form, e.g. `every 2 months`.
+    pub fn describe(&self, lang: Lang) -> String {
+        match lang {
+            Lang::En => self.describe_en(),
+            Lang::Ru => self.describe_ru(),
+        }
+    }
+
+    fn describe_en(&self) -> String {
+        match self {
+            Recurrence::Interval(period) => format!("every {}", format_duration(*period, Lang::En)),
+            Recurrence::Months(12) => "every year".to_owned(),
+            Recurrence::Months(n) if n % 12 == 0 => format!("every {} years", n / 12),
+            Recurrence::Months(1) => "every month".to_owned(),
+            Recurrence::Months(n) => format!("every {n} months"),
+            Recurrence::LastDayOfMonth => "on the last day of every month".to_owned(),
+            Recurrence::WeekdayOfMonth { nth, weekday } => {
+                let nth = match nth {
+                    -1 => "last",
+                    1 => "1st",
+                    2 => "2nd",
+                    3 => "3rd",
+                    4 => "4th",
+                    _ => "5th",
+                };
+                format!("on the {nth} {weekday} of every month")
+            }
+            Recurrence::Weekdays(days) if days.len() == 7 => "every day".to_owned(),
+            Recurrence::Weekdays(days) => format!("every {}", weekday_list(days, Lang::En)),
+            Recurrence::Cron(cron) => format!("by cron \"{}\"", cron.as_str()),
+        }
+    }
+
+    fn describe_ru(&self) -> String {
+        match self {
+            Recurrence::Interval(period) => {
+                format!("каждые {}", format_duration(*period, Lang::Ru))
+            }
+            Recurrence::Months(12) => "каждый год".to_owned(),
+            Recurrence::Months(n) if n % 12 == 0 => {
+                let years = n / 12;
+                format!("каждые {years} {}", plural_ru(years, "год", "года", "лет"))
+            }
+            Recurrence::Months(1) => "каждый месяц".to_owned(),
+            Recurrence::Months(n) => {
+                format!("каждые {n} {}", plural_ru(*n, "месяц", "месяца", "месяцев"))
+            }
+            Recurrence::LastDayOfMonth => "в последний день каждого месяца".to_owned(),
+            Recurrence::WeekdayOfMonth { nth, weekday } => {
+                let nth = match nth {
+                    -1 => "последний".to_owned(),
+                    n => format!("{n}-й"),
+                };
+                format!("каждый месяц, {nth} {}", Lang::Ru.weekday_name(*weekday))
+            }
+            Recurrence::Weekdays(days) if days.len() == 7 => "каждый день".to_owned(),
+            Recurrence::Weekdays(days) => format!("каждый {}", weekday_list(days, Lang::Ru)),
+            Recurrence::Cron(cron) => format!("по cron \"{}\"", cron.as_str()),
+        }
+    }
+
+    fn next_calendar_occurrence(
+        &self,
+        start: DateTime<Utc>,
+        tz: &Tz,
+        now: DateTime<Utc>,
+    ) -> DateTime<Utc> {
+        let local_start = start.with_timezone(tz).naive_local();
+        let start_month = month_index(local_start.date());
+        let now_month = month_index(now.with_timezone(tz).date_naive());
+        let not_before = start.max(now);
+
+        // previous month may still hold an occurrence due to time zone shift
+        let mut month = start_month.max(now_month - 1);
+        loop {
+            if let Some(date) = self.date_in_month(local_start.date(), start_month, month) {
+                let event_time = local_to_utc(tz, date.and_time(local_start.time()));
+                if event_time >= not_before {
+                    return event_time;
+                }
+            }
+            month += 1;
+        }
+    }
+
+    /// Occurrence date in `month` (index from `month_index`), if any.
+    fn date_in_month(&self, start: NaiveDate, start_month: i32, month: i32) -> Option<NaiveDate> {
+        let (year, month0) = (month.div_euclid(12), month.rem_euclid(12) as u32);
+        let first = NaiveDate::from_ymd_opt(year, month0 + 1, 1)?;
+        let last = first + chrono::Months::new(1) - Duration::days(1);
+        match self {
+            Recurrence::Interval(_) | Recurrence::Weekdays(_) | Recurrence::Cron(_) => None,
+            Recurrence::Months(n) => {
+                if (month - start_month) % (*n).max(1) as i32 != 0 {
+                    return None;
+                }
+                first.with_day(start.day().min(last.day()))
+            }
+            Recurrence::LastDayOfMonth => Some(last),
+            Recurrence::WeekdayOfMonth { nth, weekday } => {
+                if *nth < 0 {
+                    let back = (7 + last.weekday().num_days_from_monday()
+                        - weekday.num_days_from_monday())
+                        % 7;
+                    return Some(last - Duration::days(back as i64));
+                }
+                NaiveDate::from_weekday_of_month_opt(year, month0 + 1, *weekday, *nth as u8)
+            }
+        }
+    }
+}
+
+fn next_weekday_occurrence(
+    start: DateTime<Utc>,
+    days: &[Weekday],
+    tz: &Tz,
+    now: DateTime<Utc>,
+) -> DateTime<Utc> {
+    let local_start = start.with_timezone(tz).naive_local();
+    let not_before = start.max(now);
+    // previous day may still hold an occurrence due to time zone shift
+    let mut date = local_start
+        .date()
+        .max(not_before.with_timezone(tz).date_naive() - Duration::days(1));
+    // two weeks are enough to hit any day, empty set falls back to every day
+    for _ in 0..15 {
+        if days.is_empty() || days.contains(&date.weekday()) {
+            let event_time = local_to_utc(tz, date.and_time(local_start.time()));
+            if event_time >= not_before {
+                return event_time;
+            }
+        }
+        date += Duration::days(1);
+    }
+    unreachable!("weekday occurrence not found in two weeks")
+}
+
+fn month_index(date: NaiveDate) -> i32 {
+    date.year() * 12 + date.month0() as i32
+}
+
+/// Compact form like `1d2h30m`.
+pub fn format_duration(duration: Duration, lang: Lang) -> String {
+    let units = match lang {
+        Lang::En => ["d", "h", "m", "s"],
+        Lang::Ru => ["д", "ч", "м", "с"],
+    };
+    let mut rest = duration.num_seconds();
+    let mut result = String::new();
+    for (unit, secs) in units.into_iter().zip([60 * 60 * 24, 60 * 60, 60, 1]) {
+        if rest >= secs {
+            result += &format!("{}{unit}", rest / secs);
+            rest %= secs;
+        }
+    }
+    result
+}
+
+/// `Mon, Wed, Fri`
+fn weekday_list(days: &[Weekday], lang: Lang) -> String {
+    let days: Vec<String> = days.iter().map(|d| lang.weekday_name(*d)).collect();
+    days.join(", ")
+}
+
+/// Nearest occurrence not earlier than `now` of a rule started at `start`
+/// and repeated every `period`.
+///
+/// Periods of a day or longer are stepped in the local calendar of `tz`, so
+/// "daily at 09:00" stays at 09:00 across DST switches. Shorter periods keep
+/// fixed interval semantics.
+fn next_interval_occurrence(
+    start: DateTime<Utc>,
+    period: Duration,
+    tz: &Tz,
+    now: DateTime<Utc>,
+) -> DateTime<Utc> {
+    let period = if period <= Duration::zero() {
+        Duration::seconds(1) // TODO: make propper error handling
+    } else {
+        period
+    };
+    if start >= now {
+        return start;
+    }
+    let passed = (now - start).num_seconds() / period.num_seconds();
+
+    if period < Duration::days(1) {
+        let mut event_time = start + period * passed as i32;
+        while event_time < now {
+            event_time += period;
+        }
+        return event_time;
+    }
+
+    let local_start = start.with_timezone(tz).naive_local();
+    // wall clock may differ from the fixed interval by DST shift, step back once to be sure
+    let mut step = (passed - 1).max(0);
+    loop {
+        let event_time = local_to_utc(tz, local_start + period * step as i32);
+        if event_time >= now {
+            return event_time;
+        }
+        step += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Europe;
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn daily_keeps_wall_clock() {
+        // 09:00 in Berlin, CET before 2026-03-29 and CEST after
+        let start = utc(2026, 3, 20, 8, 0);
+        let day = Duration::days(1);
+        let tz = Europe::Berlin;
+        assert_eq!(
+            next_interval_occurrence(start, day, &tz, utc(2026, 3, 28, 9, 0)),
+            utc(2026, 3, 29, 7, 0)
+        );
+        assert_eq!(
+            next_interval_occurrence(start, day, &tz, utc(2026, 6, 1, 0, 0)),
+            utc(2026, 6, 1, 7, 0)
+        );
+        // and back in winter
+        assert_eq!(
+            next_interval_occurrence(start, day * 7, &tz, utc(2026, 11, 1, 0, 0)),
+            utc(2026, 11, 6, 8, 0)
+        );
+    }
+
+    #[test]
+    fn sub_day_keeps_interval() {
+        let start = utc(2026, 3, 28, 20, 0);
+        let period = Duration::hours(12);
+        let tz = Europe::Berlin;
+        assert_eq!(
+            next_interval_occurrence(start, period, &tz, utc(2026, 3, 29, 10, 0)),
+            utc(2026, 3, 29, 20, 0)
+        );
+    }
+
+    #[test]
+    fn monthly_clamps_short_months() {
+        let tz = Europe::Berlin;
+        // 31 Jan 2026 10:00 local
+        let start = utc(2026, 1, 31, 9, 0);
+        let rule = Recurrence::Months(1);
+        let expect = [
+            (utc(2026, 2, 1, 0, 0), utc(2026, 2, 28, 9, 0)),
+            (utc(2026, 3, 1, 0, 0), utc(2026, 3, 31, 8, 0)),
+            (utc(2026, 4, 1, 0, 0), utc(2026, 4, 30, 8, 0)),
+        ];
+        for (now, next) in expect {
+            assert_eq!(rule.next_occurrence(start, &tz, now), next);
+        }
+        let every_two = Recurrence::Months(2);
+        assert_eq!(
+            every_two.next_occurrence(start, &tz, utc(2026, 2, 1, 0, 0)),
+            utc(2026, 3, 31, 8, 0)
+        );
+    }
+
+    #[test]
+    fn yearly_on_leap_day() {
+        let tz = chrono_tz::UTC;
+        let start = utc(2024, 2, 29, 12, 0);
+        let rule = Recurrence::Months(12);
+        assert_eq!(
+            rule.next_occurrence(start, &tz, utc(2024, 3, 1, 0, 0)),
+            utc(2025, 2, 28, 12, 0)
+        );
+        assert_eq!(
+            rule.next_occurrence(start, &tz, utc(2027, 3, 1, 0, 0)),
+            utc(2028, 2, 29, 12, 0)
+        );
+    }
+
+    #[test]
+    fn day_of_month_rules() {
+        let tz = chrono_tz::UTC;
+        let start = utc(2026, 1, 10, 9, 0);
+        let now = utc(2026, 2, 1, 0, 0);
+        assert_eq!(
+            Recurrence::LastDayOfMonth.next_occurrence(start, &tz, start),
+            utc(2026, 1, 31, 9, 0)
+        );
+        assert_eq!(
+            Recurrence::LastDayOfMonth.next_occurrence(start, &tz, now),
+            utc(2026, 2, 28, 9, 0)
+        );
+        let second_tuesday = Recurrence::WeekdayOfMonth {
+            nth: 2,
+            weekday: Weekday::Tue,
+        };
+        assert_eq!(
+            second_tuesday.next_occurrence(start, &tz, start),
+            utc(2026, 1, 13, 9, 0)
+        );
+        assert_eq!(
+            second_tuesday.next_occurrence(start, &tz, now),
+            utc(2026, 2, 10, 9, 0)
+        );
+        let last_friday = Recurrence::WeekdayOfMonth {
+            nth: -1,
+            weekday: Weekday::Fri,
+        };
+        assert_eq!(
+            last_friday.next_occurrence(start, &tz, now),
+            utc(2026, 2, 27, 9, 0)
+        );
+        // February 2026 has only four Sundays
+        let fifth_sunday = Recurrence::WeekdayOfMonth {
+            nth: 5,
+            weekday: Weekday::Sun,
+        };
+        assert_eq!(
+            fifth_sunday.next_occurrence(start, &tz, now),
+            utc(2026, 3, 29, 9, 0)
+        );
+    }
+
+    #[test]
+    fn weekday_set() {
+        use Weekday::*;
+        let tz = Europe::Berlin;
+        // Monday 2026-03-23 08:30 local
+        let start = utc(2026, 3, 23, 7, 30);
+        let rule = Recurrence::Weekdays(vec![Mon, Wed, Fri]);
+        let expect = [
+            (utc(2026, 3, 23, 0, 0), utc(2026, 3, 23, 7, 30)),
+            (utc(2026, 3, 23, 8, 0), utc(2026, 3, 25, 7, 30)),
+            (utc(2026, 3, 27, 7, 31), utc(2026, 3, 30, 6, 30)),
+        ];
+        for (now, next) in expect {
+            assert_eq!(rule.next_occurrence(start, &tz, now), next);
+        }
+        assert_eq!(rule.describe(Lang::En), "every Mon, Wed, Fri");
+        assert_eq!(rule.describe(Lang::Ru), "каждый пн, ср, пт");
+    }
+
+    #[test]
+    fn db_round_trip() {
+        let rules = [
+            Recurrence::Interval(Duration::minutes(5)),
+            Recurrence::Months(3),
+            Recurrence::LastDayOfMonth,
+            Recurrence::WeekdayOfMonth {
+                nth: -1,
+                weekday: Weekday::Sat,
+            },
+            Recurrence::Weekdays(vec![Weekday::Mon, Weekday::Wed, Weekday::Fri]),
+            Recurrence::cron("30 9 * * 1-5").unwrap(),
+        ];
+        for rule in rules {
+            let (wait, text) = rule.to_db();
+            assert_eq!(Recurrence::from_db(wait, text), Some(rule));
+        }
+    }
+
+    #[test]
+    fn cron_in_local_time() {
+        // weekdays at 09:00 in Berlin, 2026-03-27 is Friday before the DST switch
+        let rule = Recurrence::cron("0 9 * * 1-5").unwrap();
+        let tz = Europe::Berlin;
+        let start = utc(2026, 3, 1, 0, 0);
+        assert_eq!(
+            rule.next_occurrence(start, &tz, utc(2026, 3, 27, 8, 0)),
+            utc(2026, 3, 27, 8, 0)
+        );
+        assert_eq!(
+            rule.next_occurrence(start, &tz, utc(2026, 3, 27, 8, 1)),
+            utc(2026, 3, 30, 7, 0)
+        );
+        assert!(Recurrence::cron("0 0 30 2 *").is_none());
+        assert!(Recurrence::cron("0 0 9 * * *").is_none());
+        assert!(Recurrence::cron("61 * * * *").is_none());
+    }
+
+    #[test]
+    fn start_in_future() {
+        let start = utc(2026, 3, 28, 20, 0);
+        let now = utc(2026, 3, 1, 0, 0);
+        assert_eq!(
+            next_interval_occurrence(start, Duration::days(1), &Europe::Berlin, now),
+            start
+        );
+    }
+}
@@ -14,20 +14,26 @@ use log::{debug, error, info, warn};
 use crate::{
     engine::Engine,
     keyboards::{
-        make_calendar_keyboard, make_hour_keyboard, make_main_action_keyboard, make_minute_keyboard,
+        make_calendar_keyboard, make_date_order_keyboard, make_edit_keyboard, make_fired_keyboard,
+        make_fuzzy_keyboard, make_hour_keyboard, make_language_keyboard, make_main_action_keyboard,
+        make_minute_keyboard, make_timezone_confirm_keyboard, make_timezone_keyboard,
     },
     state::FrontendCommand,
+    text_data::Lang,
 };
 
 mod command;
 mod database;
 mod engine;
+mod fuzzy;
 mod helpers;
 mod keyboards;
 mod prop_test;
+mod recurrence;
 mod sql_query;
 mod state;
 mod text_data;
+mod timezone;
 
 fn main() -> Result<()> {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("remu=debug"))
@@ -76,18 +82,48 @@ fn process_event(
     match update.content {
         UpdateContent::Message(message) => {
             let user = message.from.as_ref().expect("message has user");
-            let msg_text = message.text.as_ref().unwrap();
+            if let Some(location) = &message.location {
+                let cmds = engine.handle_location(
+                    user.id as i64,
+                    location.latitude,
+                    location.longitude,
+                    now,
+                );
+                match cmds {
+                    Ok(cmds) => {
+                        if let Err(e) = handle_command_to_frontend(front, user.id as i64, cmds) {
+                            warn!("cannot handle frontend command: {e}");
+                        }
+                    }
+                    Err(e) => {
+                        let _ = front.send_message(
+                            user.id as i64,
+                            &engine
+                                .get_user_language(user.id as i64)
+                                .processing_error(&format!("{e:#}")),
+                            None,
+                        );
+                    }
+                };
+                return;
+            }
+            let Some(msg_text) = message.text.as_ref() else {
+                warn!("Unsupported message from {}: {:?}", user.id, message);
+                return;
+            };
             match msg_text.as_str() {
                 "/start" => {
                     // very special case
-                    let res = engine.add_user(
-                        user.id as i64,
-                        user.username.as_ref().unwrap(),
-                        message.chat.id,
-                        &user.first_name,
-                        user.last_name.as_ref().unwrap(),
-                        -3,
-                    );
+                    let lang = Lang::from_code(user.language_code.as_deref());
+                    let res = engine.add_user(database::UserInfo {
+                        uid: user.id as i64,
+                        name: user.username.as_ref().unwrap(),
+                        chat_id: message.chat.id,
+                        first_name: &user.first_name,
+                        last_name: user.last_name.as_ref().unwrap(),
+                        tz: timezone::DEFAULT_TIMEZONE,
+                        lang,
+                    });
                     if let Err(e) = res {
                         error!(
                             "cannot add user, UID - <{}>, username - <{:?}>, chat_id - <{}>. Reason: {e:#}",
@@ -95,7 +131,7 @@ fn process_event(
                         );
                         let _ = front.send_message(
                             user.id as i64,
-                            &format!("cannot process message: {e}"),
+                            &lang.start_error(&e.to_string()),
                             None,
                         );
                     }
@@ -111,7 +147,9 @@ fn process_event(
                         Err(e) => {
                             let _ = front.send_message(
                                 user.id as i64,
-                                &format!("Error while state machine processing:\n\n{e:#}"),
+                                &engine
+                                    .get_user_language(user.id as i64)
+                                    .processing_error(&format!("{e:#}")),
                                 None,
                             );
                         }
@@ -150,7 +188,9 @@ fn process_event(
                 Err(e) => {
                     let _ = front.send_message(
                         user.id as i64,
-                        &format!("Error while state machine processing:\n\n{e:#}"),
+                        &engine
+                            .get_user_language(user.id as i64)
+                            .processing_error(&format!("{e:#}")),
                         None,
                     );
                 }
@@ -271,6 +311,7 @@ fn handle_command_to_frontend(
                 let keyboard = Some(make_calendar_keyboard(
                     at_calendar_command.year,
                     at_calendar_command.month as u32,
+                    at_calendar_command.lang,
                 ));
                 if let Some(msg_id) = at_calendar_command.msg_id {
                     front.edit_message(uid, msg_id, msg, keyboard)?;
@@ -279,12 +320,13 @@ fn handle_command_to_frontend(
                 }
             }
             state::FrontendCommand::keyboard(keyboard_command) => {
+                let lang = keyboard_command.lang;
                 match keyboard_command.action_type {
                     state::KeyboardCommandType::Main => {
                         front.send_message(
                             uid,
                             &keyboard_command.text,
-                            Some(make_main_action_keyboard()),
+                            Some(make_main_action_keyboard(lang)),
                         )?;
                     }
                     state::KeyboardCommandType::Hour => {
@@ -301,8 +343,60 @@ fn handle_command_to_frontend(
                             Some(make_minute_keyboard()),
                         )?;
                     }
+                    state::KeyboardCommandType::Timezone => {
+                        front.send_message(
+                            uid,
+                            &keyboard_command.text,
+                            Some(make_timezone_keyboard(lang)),
+                        )?;
+                    }
+                    state::KeyboardCommandType::TimezoneConfirm(tz) => {
+                        front.send_message(
+                            uid,
+                            &keyboard_command.text,
+                            Some(make_timezone_confirm_keyboard(&tz, lang)),
+                        )?;
+                    }
+                    state::KeyboardCommandType::DateOrder => {
+                        front.send_message(
+                            uid,
+                            &keyboard_command.text,
+                            Some(make_date_order_keyboard(lang)),
+                        )?;
+                    }
+                    state::KeyboardCommandType::Fired(id) => {
+                        front.send_message(
+                            uid,
+                            &keyboard_command.text,
+                            Some(make_fired_keyboard(id, lang)),
+                        )?;
+                    }
+                    state::KeyboardCommandType::Edit(ids) => {
+                        front.send_message(
+                            uid,
+                            &keyboard_command.text,
+                            Some(make_edit_keyboard(&ids)),
+                        )?;
+                    }
+                    state::KeyboardCommandType::Fuzzy => {
+                        front.send_message(
+                            uid,
+                            &keyboard_command.text,
+                            Some(make_fuzzy_keyboard(lang)),
+                        )?;
+                    }
+                    state::KeyboardCommandType::Language => {
+                        front.send_message(
+                            uid,
+                            &keyboard_command.text,
+                            Some(make_language_keyboard()),
+                        )?;
+                    }
                 }
             }
+            state::FrontendCommand::edit_message(edit_command) => {
+                front.edit_message(uid, edit_command.msg_id, &edit_command.text, None)?
+            }
             state::FrontendCommand::delete_message(msg_id) => front.delete_message(uid, msg_id)?,
             state::FrontendCommand::delete_keyboard(msg_id) => {
                 front.delete_keyboard(uid, msg_id)?
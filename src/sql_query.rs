@@ -5,7 +5,16 @@ pub const CREATE_USER_TABLE: &str = "CREATE TABLE IF NOT EXISTS user(
         username            TEXT NOT NULL,
         first_name          TEXT,
         last_name           TEXT,
-        timezone            INTEGER,
+        timezone            TEXT,
+        language            TEXT,
+        date_order          TEXT,
+        fuzzy               INTEGER,
+        nag_interval        INTEGER,
+        nag_max             INTEGER,
+        quiet_start         INTEGER,
+        quiet_end           INTEGER,
+        digest_time         INTEGER,
+        digest_next         INTEGER,
         chat_id             INTEGER NOT NULL
     )";
 
@@ -15,6 +24,12 @@ pub const CREATE_ACTIVE_EVENT_TABLE: &str = "CREATE TABLE IF NOT EXISTS active_e
         parent_id           INTEGER,
         event_text          TEXT NOT NULL,
         event_time          INTEGER,
+        fired_time          INTEGER,
+        snooze_count        INTEGER,
+        nag_left            INTEGER,
+        nag_time            INTEGER,
+        lead_of             INTEGER,
+        lead_seconds        INTEGER,
         FOREIGN KEY(uid)    REFERENCES user(uid)
     )";
 
@@ -24,42 +39,176 @@ pub const CREATE_REP_EVENT_TABLE: &str = "CREATE TABLE IF NOT EXISTS scheduled_e
         event_text          TEXT NOT NULL,
         event_time          INTEGER,
         event_wait          INTEGER,
+        event_rule          TEXT,
+        event_until         INTEGER,
+        event_count         INTEGER,
+        event_timezone      TEXT,
+        event_nag           INTEGER,
+        event_leads         TEXT,
         FOREIGN KEY(uid)    REFERENCES user(uid)
     )";
 
+pub const USER_TABLE_EXISTS: &str =
+    "SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name = 'user';";
+
+// Schema migrations, applied in order to databases created by older versions.
+// Fresh databases get the latest schema from CREATE_* queries and skip them.
+pub const MIGRATIONS: &[&str] = &[
+    // whole hour offsets (west positive) -> IANA names; Etc/GMT zones share the sign convention
+    "UPDATE user SET timezone = printf('Etc/GMT%+d', timezone) WHERE typeof(timezone) = 'integer';",
+    // calendar recurrence rules, NULL for fixed intervals in event_wait
+    "ALTER TABLE scheduled_event ADD COLUMN event_rule TEXT;",
+    // series end: exclusive end time and occurrences left, NULL for endless series
+    "ALTER TABLE scheduled_event ADD COLUMN event_until INTEGER;
+     ALTER TABLE scheduled_event ADD COLUMN event_count INTEGER;",
+    // interface language code, NULL for English
+    "ALTER TABLE user ADD COLUMN language TEXT;",
+    // how numeric dates are read, NULL for day-month
+    "ALTER TABLE user ADD COLUMN date_order TEXT;",
+    // 1 when time phrases are looked for in free text, NULL for disabled
+    "ALTER TABLE user ADD COLUMN fuzzy INTEGER;",
+    // zone written in the command, NULL for the user's zone
+    "ALTER TABLE scheduled_event ADD COLUMN event_timezone TEXT;",
+    // fired events are kept until done or snoozed, NULL for pending ones
+    "ALTER TABLE active_event ADD COLUMN fired_time INTEGER;
+     ALTER TABLE active_event ADD COLUMN snooze_count INTEGER;",
+    // persistent reminders: user's repeat settings (NULL for defaults), repeats
+    // left and the next one for fired events, the flag for their series
+    "ALTER TABLE user ADD COLUMN nag_interval INTEGER;
+     ALTER TABLE user ADD COLUMN nag_max INTEGER;
+     ALTER TABLE active_event ADD COLUMN nag_left INTEGER;
+     ALTER TABLE active_event ADD COLUMN nag_time INTEGER;
+     ALTER TABLE scheduled_event ADD COLUMN event_nag INTEGER;",
+    // lead alerts: the event row an alert is sent before and by how much,
+    // comma-separated seconds for series, NULL for events without alerts
+    "ALTER TABLE active_event ADD COLUMN lead_of INTEGER;
+     ALTER TABLE active_event ADD COLUMN lead_seconds INTEGER;
+     ALTER TABLE scheduled_event ADD COLUMN event_leads TEXT;",
+    // quiet hours as local minutes from midnight, NULL when not set
+    "ALTER TABLE user ADD COLUMN quiet_start INTEGER;
+     ALTER TABLE user ADD COLUMN quiet_end INTEGER;",
+    // daily digest: local minutes from midnight and the next one, NULL when off
+    "ALTER TABLE user ADD COLUMN digest_time INTEGER;
+     ALTER TABLE user ADD COLUMN digest_next INTEGER;",
+];
+
 // SQL user --------------------------------------------
 
-pub const INSERT_USER: &str = "INSERT INTO user(uid, username, first_name, last_name, chat_id, timezone) VALUES (?1, ?2, ?3, ?4, ?5, ?6);";
+pub const INSERT_USER: &str = "INSERT INTO user(uid, username, first_name, last_name, chat_id, timezone, language) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);";
 
 pub const GET_USER_TIMEZONE: &str = "SELECT timezone FROM user WHERE uid = ?1;";
 
+pub const UPDATE_USER_TIMEZONE: &str = "UPDATE user SET timezone = ?2 WHERE uid = ?1;";
+
+pub const GET_USER_LANGUAGE: &str = "SELECT language FROM user WHERE uid = ?1;";
+
+pub const UPDATE_USER_LANGUAGE: &str = "UPDATE user SET language = ?2 WHERE uid = ?1;";
+
+pub const GET_USER_DATE_ORDER: &str = "SELECT date_order FROM user WHERE uid = ?1;";
+
+pub const UPDATE_USER_DATE_ORDER: &str = "UPDATE user SET date_order = ?2 WHERE uid = ?1;";
+
+pub const GET_USER_FUZZY: &str = "SELECT fuzzy FROM user WHERE uid = ?1;";
+
+pub const UPDATE_USER_FUZZY: &str = "UPDATE user SET fuzzy = ?2 WHERE uid = ?1;";
+
+pub const GET_USER_NAG: &str = "SELECT nag_interval, nag_max FROM user WHERE uid = ?1;";
+
+pub const UPDATE_USER_NAG: &str = "UPDATE user SET nag_interval = ?2, nag_max = ?3 WHERE uid = ?1;";
+
+pub const GET_USER_QUIET: &str = "SELECT quiet_start, quiet_end FROM user WHERE uid = ?1;";
+
+pub const UPDATE_USER_QUIET: &str =
+    "UPDATE user SET quiet_start = ?2, quiet_end = ?3 WHERE uid = ?1;";
+
+pub const GET_USER_DIGEST: &str = "SELECT digest_time FROM user WHERE uid = ?1;";
+
+pub const UPDATE_USER_DIGEST: &str =
+    "UPDATE user SET digest_time = ?2, digest_next = ?3 WHERE uid = ?1;";
+
+pub const SELECT_DIGESTS_LESS_BY_TIMESTAMP: &str = "SELECT uid FROM user WHERE digest_next <= ?1;";
+
 pub const GET_ALL_USER_CHAT_ID: &str = "SELECT chat_id FROM user";
 
 // SQL one time events --------------------------------------------
 
-pub const INSERT_ACTIVE_EVENT: &str =
-    "INSERT INTO active_event(event_text, event_time, uid, parent_id) VALUES (?1, ?2, ?3, ?4);";
+pub const INSERT_ACTIVE_EVENT: &str = "INSERT INTO active_event(event_text, event_time, uid, parent_id, nag_left) VALUES (?1, ?2, ?3, ?4, ?5);";
+
+pub const INSERT_LEAD_ALERT: &str = "INSERT INTO active_event(event_text, event_time, uid, parent_id, lead_of, lead_seconds) VALUES (?1, ?2, ?3, ?4, ?5, ?6);";
+
+pub const SELECT_ACTIVE_EVENTS_LESS_BY_TIMESTAMP: &str = "SELECT id, event_text, event_time, parent_id, uid, snooze_count, nag_left, lead_seconds FROM active_event WHERE event_time <= ?1 AND fired_time IS NULL;";
+
+pub const MARK_ACTIVE_EVENT_FIRED: &str = "UPDATE active_event SET fired_time = ?2, nag_time = CASE WHEN nag_left > 0 THEN ?3 END WHERE id = ?1;";
+
+pub const DEFER_ACTIVE_EVENT: &str = "UPDATE active_event SET event_time = ?2 WHERE id = ?1;";
+
+pub const DELETE_ACTIVE_EVENT: &str = "DELETE FROM active_event WHERE id = ?1;";
 
-pub const SELECT_ACTIVE_EVENTS_LESS_BY_TIMESTAMP: &str =
-    "SELECT id, event_text, event_time, parent_id, uid FROM active_event WHERE event_time <= ?1;";
+pub const SELECT_PENDING_EVENT: &str = "SELECT event_text, event_time, parent_id FROM active_event WHERE id = ?1 AND uid = ?2 AND fired_time IS NULL AND lead_of IS NULL;";
 
-pub const DELETE_FROM_ACTIVE_EVENT_BY_ID: &str = "DELETE FROM active_event WHERE id = ?1;";
+pub const SELECT_PENDING_EVENT_NAG: &str = "SELECT nag_left IS NOT NULL FROM active_event WHERE id = ?1 AND uid = ?2 AND fired_time IS NULL;";
+
+pub const UPDATE_PENDING_EVENT: &str = "UPDATE active_event SET event_text = ?3, event_time = ?4 WHERE id = ?1 AND uid = ?2 AND fired_time IS NULL AND lead_of IS NULL;";
+
+pub const UPDATE_PENDING_EVENT_TEXT: &str = "UPDATE active_event SET event_text = ?3 WHERE (id = ?1 OR lead_of = ?1) AND uid = ?2 AND fired_time IS NULL;";
+
+pub const DELETE_PENDING_EVENT: &str =
+    "DELETE FROM active_event WHERE (id = ?1 OR lead_of = ?1) AND uid = ?2 AND fired_time IS NULL;";
+
+pub const DELETE_PENDING_LEAD_ALERTS: &str =
+    "DELETE FROM active_event WHERE lead_of = ?1 AND fired_time IS NULL;";
+
+pub const SELECT_NAGS_LESS_BY_TIMESTAMP: &str = "SELECT id, event_text, event_time, uid FROM active_event WHERE nag_time <= ?1 AND fired_time IS NOT NULL;";
+
+pub const RESEND_NAG: &str = "UPDATE active_event SET nag_left = nag_left - 1, nag_time = CASE WHEN nag_left > 1 THEN ?2 END WHERE id = ?1;";
+
+pub const DEFER_NAG: &str = "UPDATE active_event SET nag_time = ?2 WHERE id = ?1;";
+
+pub const START_NAG: &str = "UPDATE active_event SET nag_left = ?3, nag_time = ?4 WHERE id = ?1 AND uid = ?2 AND fired_time IS NOT NULL;";
+
+pub const SELECT_FIRED_EVENT: &str = "SELECT event_text, event_time FROM active_event WHERE id = ?1 AND uid = ?2 AND fired_time IS NOT NULL;";
+
+pub const SNOOZE_FIRED_EVENT: &str = "UPDATE active_event SET event_time = ?3, fired_time = NULL, nag_time = NULL, snooze_count = coalesce(snooze_count, 0) + 1 WHERE id = ?1 AND uid = ?2 AND fired_time IS NOT NULL;";
+
+pub const DELETE_FIRED_EVENT: &str =
+    "DELETE FROM active_event WHERE id = ?1 AND uid = ?2 AND fired_time IS NOT NULL;";
+
+pub const DELETE_FIRED_EVENTS_BEFORE: &str =
+    "DELETE FROM active_event WHERE fired_time < ?1 AND nag_time IS NULL;";
 
 pub const DELETE_FROM_ACTIVE_EVENT_BY_PARENT_ID: &str =
     "DELETE FROM active_event WHERE parent_id = ?1;";
 
-pub const MIN_TIMESTAMP_FROM_ACTIVE_EVENT: &str = "SELECT min(event_time) FROM active_event;";
+pub const DELETE_PENDING_BY_PARENT_ID: &str =
+    "DELETE FROM active_event WHERE parent_id = ?1 AND fired_time IS NULL;";
+
+pub const UPDATE_PENDING_TEXT_BY_PARENT_ID: &str =
+    "UPDATE active_event SET event_text = ?2 WHERE parent_id = ?1 AND fired_time IS NULL;";
+
+pub const MIN_WAKEUP_TIMESTAMP: &str = "SELECT min(t) FROM (SELECT CASE WHEN fired_time IS NULL THEN event_time ELSE nag_time END AS t FROM active_event UNION ALL SELECT digest_next FROM user);";
+
+pub const SELECT_ONE_TIME_EVENTS_BETWEEN: &str = "SELECT event_text, event_time FROM active_event WHERE uid = ?1 AND fired_time IS NULL AND lead_of IS NULL AND (parent_id = -1 OR snooze_count IS NOT NULL) AND event_time >= ?2 AND event_time < ?3 ORDER BY event_time;";
 
-pub const SELECT_ALL_ACTIVE_EVENT_BY_UID_LIMIT: &str = "SELECT id, event_text, event_time FROM active_event WHERE uid = ?1 ORDER BY event_time LIMIT 20;";
+pub const SELECT_ALL_ACTIVE_EVENT_BY_UID_LIMIT: &str = "SELECT a.id, a.event_text, a.event_time, s.event_wait, s.event_rule, a.parent_id, (SELECT group_concat(l.lead_seconds) FROM active_event l WHERE l.lead_of = a.id AND l.fired_time IS NULL) FROM active_event a LEFT JOIN scheduled_event s ON a.parent_id = s.id WHERE a.uid = ?1 AND a.fired_time IS NULL AND a.lead_of IS NULL ORDER BY a.event_time LIMIT 20;";
 
 // SQL rep events ------------------------------------------------
 
-pub const INSERT_REP_EVENT: &str =
-    "INSERT INTO scheduled_event(event_text, event_time, event_wait, uid) VALUES (?1, ?2, ?3, ?4);";
+pub const INSERT_REP_EVENT: &str = "INSERT INTO scheduled_event(event_text, event_time, event_wait, event_rule, uid, event_until, event_count, event_timezone, event_nag, event_leads) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10);";
 
-pub const SELECT_REP_BY_ID: &str =
-    "SELECT id, event_text, event_time, event_wait FROM scheduled_event WHERE id = ?1;";
+pub const SELECT_REP_BY_ID: &str = "SELECT s.id, s.event_text, s.event_time, s.event_wait, coalesce(s.event_timezone, u.timezone), s.event_rule, s.event_until, s.event_count, s.event_nag, s.event_leads FROM scheduled_event s JOIN user u ON s.uid = u.uid WHERE s.id = ?1;";
+
+pub const UPDATE_REP_EVENT: &str = "UPDATE scheduled_event SET event_text = ?3, event_time = ?4, event_wait = ?5, event_rule = ?6, event_until = ?7, event_count = ?8, event_timezone = ?9, event_leads = ?10 WHERE id = ?1 AND uid = ?2;";
+
+pub const UPDATE_REP_TEXT: &str = "UPDATE scheduled_event SET event_text = ?2 WHERE id = ?1;";
+
+pub const DECREMENT_REP_COUNT_BY_ID: &str =
+    "UPDATE scheduled_event SET event_count = event_count - 1 WHERE id = ?1;";
 
 pub const DELETE_FROM_REP_BY_ID: &str = "DELETE FROM scheduled_event WHERE id = ?1;";
 
-pub const SELECT_ALL_REP_BY_UID_LIMIT: &str = "SELECT id, event_text, event_time, event_wait FROM scheduled_event WHERE uid = ?1 ORDER BY event_time LIMIT 20;";
+pub const DELETE_FROM_REP_BY_ID_AND_UID: &str =
+    "DELETE FROM scheduled_event WHERE id = ?1 AND uid = ?2;";
+
+pub const SELECT_REP_BY_ID_AND_UID: &str = "SELECT id, event_text, event_time, event_wait, event_rule, event_until, event_count, event_timezone, event_leads FROM scheduled_event WHERE id = ?1 AND uid = ?2;";
+
+pub const SELECT_ALL_REP_BY_UID_LIMIT: &str = "SELECT id, event_text, event_time, event_wait, event_rule, event_until, event_count, event_timezone, event_leads FROM scheduled_event WHERE uid = ?1 ORDER BY event_time LIMIT 20;";
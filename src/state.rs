@@ -1,17 +1,17 @@
-use anyhow::{Result, anyhow, bail};
+use anyhow::{Context, Result, anyhow, bail};
 use chrono::prelude::*;
+use chrono_tz::Tz;
 use log::debug;
 use log::error;
 use log::warn;
 
-use crate::database::DataBase;
+use crate::command::{Command, DATE_ORDERS, ParseError};
+use crate::database::{DataBase, NagSettings, QuietHours};
 use crate::engine::ProcessResult;
+use crate::fuzzy::extract_command;
 use crate::helpers::*;
-use crate::text_data;
-
-pub const EXPECT_DURATION_MSG: &str = "Ok, now write time duration.";
-pub const EXPECT_TIME_MSG: &str = "Ok, now write the time of event";
-pub const EXPECT_BUTTON_PUSH: &str = "Ok, now choose";
+use crate::text_data::{LANGUAGES, Lang};
+use crate::timezone::{describe_timezone, local_to_utc, parse_timezone};
 
 // FIXME: make struct derive from String
 #[derive(Clone, Debug, PartialEq)]
@@ -25,21 +25,38 @@ pub struct AtCalendarCommand {
     pub action_type: String,
     pub year: i32,
     pub month: i32,
-    pub tz: i32,
+    pub tz: Tz,
+    pub lang: Lang,
     pub message: String,
     pub msg_id: Option<i32>,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct EditMessageCommand {
+    pub msg_id: i32,
+    pub text: String,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct KeyboardCommand {
     pub action_type: KeyboardCommandType,
     pub text: String,
+    pub lang: Lang,
 }
 #[derive(Clone, Debug, PartialEq)]
 pub enum KeyboardCommandType {
     Main,
     Hour,
     Minute,
+    Timezone,
+    TimezoneConfirm(Tz),
+    Language,
+    DateOrder,
+    Fuzzy,
+    /// Buttons under a fired reminder, with the id of its row.
+    Fired(i64),
+    /// Edit buttons under a listing, with the ids of listed rows.
+    Edit(Vec<i64>),
 }
 
 #[allow(non_camel_case_types)]
@@ -48,6 +65,7 @@ pub enum FrontendCommand {
     send(SendMessageCommand),
     calendar(AtCalendarCommand),
     keyboard(KeyboardCommand),
+    edit_message(EditMessageCommand),
     delete_message(i32),
     delete_keyboard(i32),
 }
@@ -74,6 +92,10 @@ pub enum UserState {
     AtTimeText(AtTimeText),
     AfterInput(AfterInput),
     RepDeleteChoose(RepDeleteChoose),
+    TimezoneChoose(TimezoneChoose),
+    FuzzyConfirm(FuzzyConfirm),
+    SnoozeInput(SnoozeInput),
+    EditInput(EditInput),
 }
 
 impl UserState {
@@ -87,11 +109,15 @@ impl UserState {
         match self {
             UserState::ReadyToProcess => ready_process(data, now, db),
             UserState::AtCalendar(_) => Err(anyhow!("AtCalendar state cannot handle text input")),
-            UserState::AtTimeHour(state) => Ok(state.process(data)),
+            UserState::AtTimeHour(state) => Ok(state.process(data, db)),
             UserState::AtTimeMinute(state) => state.process(data, now, db),
             UserState::AtTimeText(state) => state.process(data, now, db),
             UserState::AfterInput(state) => state.process(data, now, db),
             UserState::RepDeleteChoose(state) => Ok(state.process(data, db)),
+            UserState::TimezoneChoose(state) => state.process(data, now, db),
+            UserState::FuzzyConfirm(_) => ready_process(data, now, db),
+            UserState::SnoozeInput(state) => state.process(data, now, db),
+            UserState::EditInput(state) => state.process(data, now, db),
         }
     }
 
@@ -104,11 +130,15 @@ impl UserState {
         match self {
             UserState::ReadyToProcess => ready_process_keyboard(data, now, db),
             UserState::AtCalendar(state) => Ok(state.process_keyboard(data, now)),
-            UserState::AtTimeHour(state) => Ok(state.process_keyboard(data)),
+            UserState::AtTimeHour(state) => Ok(state.process_keyboard(data, db)),
             UserState::AtTimeMinute(state) => state.process_keyboard(data, now, db),
             UserState::AtTimeText(state) => Ok(state.process_keyboard()),
             UserState::AfterInput(_) => Err(anyhow!("expect not button, but text")),
-            UserState::RepDeleteChoose(state) => Ok(state.process_keyboard()),
+            UserState::RepDeleteChoose(state) => Ok(state.process_keyboard(data, db)),
+            UserState::TimezoneChoose(state) => state.process_keyboard(data, now, db),
+            UserState::FuzzyConfirm(state) => state.process_keyboard(data, now, db),
+            UserState::SnoozeInput(_) => ready_process_keyboard(data, now, db),
+            UserState::EditInput(_) => ready_process_keyboard(data, now, db),
         }
     }
 
@@ -121,6 +151,10 @@ impl UserState {
             UserState::AtTimeText(_) => "at_time_text",
             UserState::AfterInput(_) => "after_input",
             UserState::RepDeleteChoose(_) => "rep_delete_choose",
+            UserState::TimezoneChoose(_) => "timezone_choose",
+            UserState::FuzzyConfirm(_) => "fuzzy_confirm",
+            UserState::SnoozeInput(_) => "snooze_input",
+            UserState::EditInput(_) => "edit_input",
         }
     }
 }
@@ -167,6 +201,29 @@ pub struct RepDeleteChoose {
     list_id: Vec<i64>,
 }
 
+#[derive(Clone, Debug)]
+pub struct TimezoneChoose;
+
+/// Fired reminder waiting for the user to say when to repeat it.
+#[derive(Clone, Debug)]
+pub struct SnoozeInput {
+    id: i64,
+    msg_id: i32,
+    msg_text: String,
+}
+
+/// Pending reminder waiting for its new time or text.
+#[derive(Clone, Debug)]
+pub struct EditInput {
+    id: i64,
+}
+
+/// Strict command line built from a time phrase found in free text.
+#[derive(Clone, Debug)]
+pub struct FuzzyConfirm {
+    line: String,
+}
+
 fn ready_start_calendar(
     id: i64,
     _input: &str,
@@ -177,16 +234,17 @@ fn ready_start_calendar(
 ) -> ProcessResult {
     debug!("ReadyToProcess::start_Calendar");
     let tz = db.get_user_timezone(id);
-    let dt = chrono::Duration::seconds((tz as i64) * 60 * 60);
-    let prev = now - dt;
+    let lang = db.get_user_language(id);
+    let prev = now.with_timezone(&tz);
 
     let command = AtCalendarCommand {
         action_type: "calendar".to_string(),
         month: prev.month() as i32,
         year: prev.year(),
         tz,
+        lang,
         msg_id,
-        message: EXPECT_BUTTON_PUSH.to_string() + " date",
+        message: lang.choose_date().to_string(),
     };
 
     ProcessResult::single(
@@ -203,43 +261,65 @@ fn ready_process(
     now: DateTime<Utc>,
     db: &mut DataBase,
 ) -> Result<ProcessResult> {
+    let lang = db.get_user_language(data.uid);
     if !data.input.starts_with('/') {
-        if let Some(ret_text) = process_text_command(data.uid, &data.input, now, db) {
-            return Ok(ProcessResult::msg_send(ret_text, UserState::ReadyToProcess));
+        let result = if data.input.trim().contains('\n') {
+            process_multiline_command(data.uid, &data.input, now, db)
         } else {
-            let command = KeyboardCommand {
-                action_type: KeyboardCommandType::Main,
-                text: data.input.to_string(),
-            };
-            return Ok(ProcessResult::single(
-                FrontendCommand::keyboard(command),
-                Some(UserState::ReadyToProcess),
-            ));
+            process_text_command(data.uid, &data.input, now, db)
+        };
+        match result {
+            Ok(ret_text) => {
+                return Ok(ProcessResult::msg_send(ret_text, UserState::ReadyToProcess));
+            }
+            Err(ParseError::NoMatch) => {
+                if let Some(result) = propose_fuzzy_command(&data, now, db) {
+                    return Ok(result);
+                }
+                let command = KeyboardCommand {
+                    action_type: KeyboardCommandType::Main,
+                    text: data.input.to_string(),
+                    lang,
+                };
+                return Ok(ProcessResult::single(
+                    FrontendCommand::keyboard(command),
+                    Some(UserState::ReadyToProcess),
+                ));
+            }
+            Err(e) => {
+                return Ok(ProcessResult::msg_send(
+                    lang.parse_error(&e),
+                    UserState::ReadyToProcess,
+                ));
+            }
         }
     }
     let result = match data.input.as_ref() {
-        "/help more" => ProcessResult::msg_send(
-            text_data::DETAILED_HELP_MESSAGE_RU.to_owned(),
-            UserState::ReadyToProcess,
-        ),
+        "/help more" => {
+            ProcessResult::msg_send(lang.detailed_help().to_owned(), UserState::ReadyToProcess)
+        }
 
-        "/help" => ProcessResult::msg_send(
-            text_data::MAIN_HELP_MESSAGE_RU.to_owned(),
-            UserState::ReadyToProcess,
-        ),
+        "/help" => ProcessResult::msg_send(lang.main_help().to_owned(), UserState::ReadyToProcess),
 
-        "/list" => {
-            let list = get_active_event_list(data.uid, db);
-            let ret_text = if list.is_empty() {
-                "No current active event".to_owned()
-            } else {
-                list.iter()
-                    .enumerate()
-                    .fold(String::from(""), |s, (i, val)| {
-                        s + &format!("{}) {}\n", i + 1, val)
-                    })
-            };
-            ProcessResult::msg_send(ret_text, UserState::ReadyToProcess)
+        "/list" => list_active_events(data.uid, "", db),
+
+        "/edit" => list_active_events(data.uid, lang.edit_header(), db),
+
+        cmd if cmd.starts_with("/edit ") => {
+            let (_, list_id) = get_active_event_list(data.uid, db);
+            match cmd["/edit ".len()..].trim().parse::<usize>() {
+                Ok(number) if (1..=list_id.len()).contains(&number) => {
+                    start_edit(data.uid, list_id[number - 1], db)
+                }
+                Ok(_) => ProcessResult::msg_send(
+                    lang.number_out_of_limit().to_string(),
+                    UserState::ReadyToProcess,
+                ),
+                Err(_) => ProcessResult::msg_send(
+                    lang.expect_number().to_string(),
+                    UserState::ReadyToProcess,
+                ),
+            }
         }
 
         "/at" => ready_start_calendar(data.uid, &data.input, None, None, db, now),
@@ -248,31 +328,406 @@ fn ready_process(
             let (list_str, list_id) = get_rep_event_list(data.uid, db);
             if list_str.is_empty() {
                 return Ok(ProcessResult::msg_send(
-                    "No current rep event".to_owned(),
+                    lang.no_rep_events().to_owned(),
                     UserState::ReadyToProcess,
                 ));
             }
-            let ret_str = "Here is yout rep events list. Choose witch to delete:\n".to_string()
-                + &list_str.join("\n");
+            let ret_str = list_str
+                .iter()
+                .enumerate()
+                .fold(lang.rep_delete_header().to_string(), |s, (i, val)| {
+                    s + &format!("{}) {}\n", i + 1, val)
+                });
             ProcessResult::msg_send(
                 ret_str,
                 UserState::RepDeleteChoose(RepDeleteChoose { list_id }),
             )
         }
 
-        _ => {
-            bail!("Unknown command: {}", data.input);
+        "/timezone" => {
+            let tz = db.get_user_timezone(data.uid);
+            let text = lang.timezone_info(
+                &describe_timezone(&tz, now),
+                &now.with_timezone(&tz).format("%H:%M").to_string(),
+            );
+            ProcessResult::single(
+                FrontendCommand::keyboard(KeyboardCommand {
+                    action_type: KeyboardCommandType::Timezone,
+                    text,
+                    lang,
+                }),
+                Some(UserState::TimezoneChoose(TimezoneChoose)),
+            )
+        }
+
+        cmd if cmd.starts_with("/timezone ") => {
+            change_timezone(data.uid, &cmd["/timezone ".len()..], now, db)?
+        }
+
+        "/language" => ProcessResult::single(
+            FrontendCommand::keyboard(KeyboardCommand {
+                action_type: KeyboardCommandType::Language,
+                text: lang.language_prompt(),
+                lang,
+            }),
+            Some(UserState::ReadyToProcess),
+        ),
+
+        cmd if cmd.starts_with("/language ") => {
+            change_language(data.uid, &cmd["/language ".len()..], db)?
+        }
+
+        "/date_order" => ProcessResult::single(
+            FrontendCommand::keyboard(KeyboardCommand {
+                action_type: KeyboardCommandType::DateOrder,
+                text: lang.date_order_prompt(db.get_user_date_order(data.uid)),
+                lang,
+            }),
+            Some(UserState::ReadyToProcess),
+        ),
+
+        cmd if cmd.starts_with("/date_order ") => {
+            change_date_order(data.uid, &cmd["/date_order ".len()..], db)?
+        }
+
+        "/parse" => {
+            ProcessResult::msg_send(lang.parse_usage().to_owned(), UserState::ReadyToProcess)
         }
+
+        cmd if cmd.starts_with("/parse ") => {
+            let text = match format_parse_preview(data.uid, cmd["/parse ".len()..].trim(), now, db)
+            {
+                Ok(text) => text,
+                Err(e) => lang.parse_error(&e),
+            };
+            ProcessResult::msg_send(text, UserState::ReadyToProcess)
+        }
+
+        "/nag" => {
+            let nag = db.get_user_nag(data.uid);
+            ProcessResult::msg_send(
+                lang.nag_info(nag.interval_minutes, nag.max_repeats),
+                UserState::ReadyToProcess,
+            )
+        }
+
+        cmd if cmd.starts_with("/nag ") => change_nag(data.uid, &cmd["/nag ".len()..], db)?,
+
+        "/quiet" => {
+            let info = db.get_user_quiet(data.uid).map(|quiet| {
+                (
+                    quiet.start.format("%H:%M").to_string(),
+                    quiet.end.format("%H:%M").to_string(),
+                )
+            });
+            ProcessResult::msg_send(lang.quiet_info(info), UserState::ReadyToProcess)
+        }
+
+        cmd if cmd.starts_with("/quiet ") => change_quiet(data.uid, &cmd["/quiet ".len()..], db)?,
+
+        "/digest" => {
+            let time = db
+                .get_user_digest(data.uid)
+                .map(|time| time.format("%H:%M").to_string());
+            ProcessResult::msg_send(lang.digest_info(time), UserState::ReadyToProcess)
+        }
+
+        cmd if cmd.starts_with("/digest ") => {
+            change_digest(data.uid, &cmd["/digest ".len()..], now, db)?
+        }
+
+        "/fuzzy" => ProcessResult::msg_send(
+            lang.fuzzy_info(db.get_user_fuzzy(data.uid)).to_owned(),
+            UserState::ReadyToProcess,
+        ),
+
+        cmd if cmd.starts_with("/fuzzy ") => {
+            let enabled = match cmd["/fuzzy ".len()..].trim() {
+                input if input.eq_ignore_ascii_case("on") => true,
+                input if input.eq_ignore_ascii_case("off") => false,
+                input => {
+                    return Ok(ProcessResult::msg_send(
+                        lang.unknown_fuzzy(input),
+                        UserState::ReadyToProcess,
+                    ));
+                }
+            };
+            db.set_user_fuzzy(data.uid, enabled)?;
+            ProcessResult::msg_send(
+                lang.fuzzy_set(enabled).to_owned(),
+                UserState::ReadyToProcess,
+            )
+        }
+
+        _ => ProcessResult::msg_send(lang.unknown_command(&data.input), UserState::ReadyToProcess),
     };
     Ok(result)
 }
 
+/// Looks for a time phrase in a single line message the strict grammar didn't take.
+fn propose_fuzzy_command(
+    data: &TextEventData,
+    now: DateTime<Utc>,
+    db: &mut DataBase,
+) -> Option<ProcessResult> {
+    if data.input.trim().contains('\n') || !db.get_user_fuzzy(data.uid) {
+        return None;
+    }
+    let tz = db.get_user_timezone(data.uid);
+    let order = db.get_user_date_order(data.uid);
+    let (line, command) = extract_command(&data.input, now, tz, order)?;
+    debug!("Fuzzy command proposed: {}", line);
+
+    let lang = db.get_user_language(data.uid);
+    let preview = format_confirmation(data.uid, &command, now, db);
+    Some(ProcessResult::single(
+        FrontendCommand::keyboard(KeyboardCommand {
+            action_type: KeyboardCommandType::Fuzzy,
+            text: lang.fuzzy_proposal(&line, &preview),
+            lang,
+        }),
+        Some(UserState::FuzzyConfirm(FuzzyConfirm { line })),
+    ))
+}
+
+/// Numbered active events with a button to edit each of them.
+fn list_active_events(uid: i64, header: &str, db: &mut DataBase) -> ProcessResult {
+    let lang = db.get_user_language(uid);
+    let (list_str, list_id) = get_active_event_list(uid, db);
+    if list_str.is_empty() {
+        return ProcessResult::msg_send(
+            lang.no_active_events().to_owned(),
+            UserState::ReadyToProcess,
+        );
+    }
+    let text = list_str
+        .iter()
+        .enumerate()
+        .fold(header.to_owned(), |s, (i, val)| {
+            s + &format!("{}) {}\n", i + 1, val)
+        });
+    ProcessResult::single(
+        FrontendCommand::keyboard(KeyboardCommand {
+            action_type: KeyboardCommandType::Edit(list_id),
+            text,
+            lang,
+        }),
+        Some(UserState::ReadyToProcess),
+    )
+}
+
+fn start_edit(uid: i64, id: i64, db: &mut DataBase) -> ProcessResult {
+    let lang = db.get_user_language(uid);
+    match db.get_pending_event(id, uid) {
+        Some((event, _)) => ProcessResult::msg_send(
+            lang.expect_edit(&event.event_text),
+            UserState::EditInput(EditInput { id }),
+        ),
+        None => {
+            ProcessResult::msg_send(lang.edit_event_gone().to_owned(), UserState::ReadyToProcess)
+        }
+    }
+}
+
+/// Edit button under a listing, `edit:<id>`.
+pub fn process_edit_keyboard(data: KeyboardEventData, db: &mut DataBase) -> Result<ProcessResult> {
+    let id: i64 = data
+        .callback_data
+        .strip_prefix("edit:")
+        .and_then(|id| id.parse().ok())
+        .context("incorrect edit button")?;
+    Ok(start_edit(data.uid, id, db))
+}
+
+impl EditInput {
+    /// A `/command` cancels the edit and is run as usual.
+    fn process(
+        &self,
+        data: TextEventData,
+        now: DateTime<Utc>,
+        db: &mut DataBase,
+    ) -> Result<ProcessResult> {
+        if data.input.starts_with('/') {
+            return ready_process(data, now, db);
+        }
+        let lang = db.get_user_language(data.uid);
+        let text = match process_edit_command(data.uid, self.id, &data.input, now, db) {
+            Ok(Some(text)) => text,
+            Ok(None) => lang.edit_event_gone().to_owned(),
+            Err(e) => lang.parse_error(&e),
+        };
+        Ok(ProcessResult::msg_send(text, UserState::ReadyToProcess))
+    }
+}
+
+fn change_nag(uid: i64, input: &str, db: &mut DataBase) -> Result<ProcessResult> {
+    let lang = db.get_user_language(uid);
+    let input = input.trim();
+    let numbers: Vec<u32> = input
+        .split_whitespace()
+        .map_while(|n| n.parse().ok())
+        .collect();
+    let nag = match numbers[..] {
+        [interval_minutes, max_repeats]
+            if (1..=1440).contains(&interval_minutes) && (1..=100).contains(&max_repeats) =>
+        {
+            NagSettings {
+                interval_minutes,
+                max_repeats,
+            }
+        }
+        _ => {
+            return Ok(ProcessResult::msg_send(
+                lang.unknown_nag(input),
+                UserState::ReadyToProcess,
+            ));
+        }
+    };
+    db.set_user_nag(uid, nag)?;
+    Ok(ProcessResult::msg_send(
+        lang.nag_set(nag.interval_minutes, nag.max_repeats),
+        UserState::ReadyToProcess,
+    ))
+}
+
+/// `/quiet 23.00 8.00`, `/quiet 23-8` or `/quiet off`.
+fn change_quiet(uid: i64, input: &str, db: &mut DataBase) -> Result<ProcessResult> {
+    let lang = db.get_user_language(uid);
+    let input = input.trim();
+    if input == "off" {
+        db.set_user_quiet(uid, None)?;
+        return Ok(ProcessResult::msg_send(
+            lang.quiet_off().to_owned(),
+            UserState::ReadyToProcess,
+        ));
+    }
+    let times: Vec<Option<NaiveTime>> = input
+        .split(|c: char| c == '-' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .map(parse_clock)
+        .collect();
+    let quiet = match times[..] {
+        [Some(start), Some(end)] if start != end => QuietHours { start, end },
+        _ => {
+            return Ok(ProcessResult::msg_send(
+                lang.unknown_quiet(input),
+                UserState::ReadyToProcess,
+            ));
+        }
+    };
+    db.set_user_quiet(uid, Some(quiet))?;
+    Ok(ProcessResult::msg_send(
+        lang.quiet_set(
+            &quiet.start.format("%H:%M").to_string(),
+            &quiet.end.format("%H:%M").to_string(),
+        ),
+        UserState::ReadyToProcess,
+    ))
+}
+
+/// `/digest 8.00` or `/digest off`.
+fn change_digest(
+    uid: i64,
+    input: &str,
+    now: DateTime<Utc>,
+    db: &mut DataBase,
+) -> Result<ProcessResult> {
+    let lang = db.get_user_language(uid);
+    let input = input.trim();
+    if input == "off" {
+        db.set_user_digest(uid, None, now)?;
+        return Ok(ProcessResult::msg_send(
+            lang.digest_off().to_owned(),
+            UserState::ReadyToProcess,
+        ));
+    }
+    let Some(time) = parse_clock(input) else {
+        return Ok(ProcessResult::msg_send(
+            lang.unknown_digest(input),
+            UserState::ReadyToProcess,
+        ));
+    };
+    db.set_user_digest(uid, Some(time), now)?;
+    Ok(ProcessResult::msg_send(
+        lang.digest_set(&time.format("%H:%M").to_string()),
+        UserState::ReadyToProcess,
+    ))
+}
+
+/// Local time of day in settings, `8`, `8.30` or `8:30`.
+fn parse_clock(text: &str) -> Option<NaiveTime> {
+    let (hour, minute) = text.split_once(['.', ':']).unwrap_or((text, "0"));
+    NaiveTime::from_hms_opt(hour.parse().ok()?, minute.parse().ok()?, 0)
+}
+
+fn change_date_order(uid: i64, input: &str, db: &mut DataBase) -> Result<ProcessResult> {
+    let lang = db.get_user_language(uid);
+    let input = input.trim();
+    let Some(order) = DATE_ORDERS
+        .iter()
+        .copied()
+        .find(|order| order.code().eq_ignore_ascii_case(input))
+    else {
+        return Ok(ProcessResult::msg_send(
+            lang.unknown_date_order(input),
+            UserState::ReadyToProcess,
+        ));
+    };
+    db.set_user_date_order(uid, order)?;
+    Ok(ProcessResult::msg_send(
+        lang.date_order_set(order),
+        UserState::ReadyToProcess,
+    ))
+}
+
+fn change_language(uid: i64, input: &str, db: &mut DataBase) -> Result<ProcessResult> {
+    let input = input.trim();
+    let Some(lang) = LANGUAGES.iter().copied().find(|lang| {
+        lang.code().eq_ignore_ascii_case(input)
+            || lang.name().to_lowercase() == input.to_lowercase()
+    }) else {
+        return Ok(ProcessResult::msg_send(
+            db.get_user_language(uid).unknown_language(input),
+            UserState::ReadyToProcess,
+        ));
+    };
+    db.set_user_language(uid, lang)?;
+    Ok(ProcessResult::msg_send(
+        lang.language_set(),
+        UserState::ReadyToProcess,
+    ))
+}
+
+fn change_timezone(
+    uid: i64,
+    input: &str,
+    now: DateTime<Utc>,
+    db: &mut DataBase,
+) -> Result<ProcessResult> {
+    let lang = db.get_user_language(uid);
+    let Some(tz) = parse_timezone(input, now) else {
+        return Ok(ProcessResult::msg_send(
+            lang.unknown_timezone(input.trim()),
+            UserState::ReadyToProcess,
+        ));
+    };
+    db.set_user_timezone(uid, tz)?;
+    // the digest follows the local clock
+    db.set_user_digest(uid, db.get_user_digest(uid), now)?;
+    let text = lang.timezone_set(
+        &describe_timezone(&tz, now),
+        &format_active_event_list(uid, db),
+    );
+    Ok(ProcessResult::msg_send(text, UserState::ReadyToProcess))
+}
+
 fn ready_process_keyboard(
     data: KeyboardEventData,
     now: DateTime<Utc>,
     db: &mut DataBase,
 ) -> Result<ProcessResult> {
     debug!("State ReadyToProcess: process_keyboard function called");
+    let lang = db.get_user_language(data.uid);
     if data.callback_data.starts_with("at") {
         Ok(ready_start_calendar(
             data.uid,
@@ -282,9 +737,27 @@ fn ready_process_keyboard(
             db,
             now,
         ))
+    } else if let Some(name) = data.callback_data.strip_prefix("tz:") {
+        let mut result = change_timezone(data.uid, name, now, db)?;
+        result
+            .frontend_command
+            .insert(0, FrontendCommand::delete_keyboard(data.msg_id));
+        Ok(result)
+    } else if let Some(code) = data.callback_data.strip_prefix("date_order:") {
+        let mut result = change_date_order(data.uid, code, db)?;
+        result
+            .frontend_command
+            .insert(0, FrontendCommand::delete_keyboard(data.msg_id));
+        Ok(result)
+    } else if let Some(code) = data.callback_data.strip_prefix("lang:") {
+        let mut result = change_language(data.uid, code, db)?;
+        result
+            .frontend_command
+            .insert(0, FrontendCommand::delete_keyboard(data.msg_id));
+        Ok(result)
     } else if data.callback_data.starts_with("after") {
         Ok(ProcessResult::msg_send(
-            EXPECT_DURATION_MSG.to_owned(),
+            lang.expect_duration().to_owned(),
             UserState::AfterInput(AfterInput {
                 ev_text: data.msg_text,
             }),
@@ -292,12 +765,15 @@ fn ready_process_keyboard(
     } else {
         let cmd_option = data.callback_data + " " + &data.msg_text;
 
-        if let Some(ret_text) = process_text_command(data.uid, &cmd_option, now, db) {
-            let ret_text = format!("Resulting command:\n{}\n{}", cmd_option, ret_text);
-            Ok(ProcessResult::msg_send(ret_text, UserState::ReadyToProcess))
-        } else {
-            warn!("incorrect query data, merged command: {}", cmd_option);
-            bail!("incorrect query data")
+        match process_text_command(data.uid, &cmd_option, now, db) {
+            Ok(ret_text) => {
+                let ret_text = lang.resulting_command(&cmd_option, &ret_text);
+                Ok(ProcessResult::msg_send(ret_text, UserState::ReadyToProcess))
+            }
+            Err(e) => {
+                warn!("incorrect query data, merged command: {}", cmd_option);
+                bail!("incorrect query data: {e}")
+            }
         }
     }
 }
@@ -341,7 +817,8 @@ impl AtCalendar {
             let del_cmd = FrontendCommand::delete_message(data.msg_id);
             let keyboard_command = FrontendCommand::keyboard(KeyboardCommand {
                 action_type: KeyboardCommandType::Hour,
-                text: EXPECT_TIME_MSG.to_string(),
+                text: self.command.lang.expect_time().to_string(),
+                lang: self.command.lang,
             });
             return ProcessResult {
                 frontend_command: vec![del_cmd, keyboard_command],
@@ -356,13 +833,15 @@ impl AtCalendar {
             // TODO: bot.send_message(chat_id, 'Ok, ' + date.strftime(r'%b %d') + '. Now write the time of event.')
             let keyboard_cmd = FrontendCommand::keyboard(KeyboardCommand {
                 action_type: KeyboardCommandType::Hour,
-                text: EXPECT_TIME_MSG.to_string(),
+                text: self.command.lang.expect_time().to_string(),
+                lang: self.command.lang,
             });
             let delete_cmd = FrontendCommand::delete_message(data.msg_id);
+            let today = now.with_timezone(&self.command.tz).date_naive();
             let now = if data.callback_data == "today" {
-                now
+                today
             } else {
-                now + chrono::Duration::days(1)
+                today + chrono::Duration::days(1)
             };
             return ProcessResult {
                 frontend_command: vec![delete_cmd, keyboard_cmd],
@@ -388,11 +867,12 @@ impl AtCalendar {
 }
 
 impl AtTimeHour {
-    fn proceed_next_stage(&self, hour: i32, msg_id: i32) -> ProcessResult {
+    fn proceed_next_stage(&self, hour: i32, msg_id: i32, lang: Lang) -> ProcessResult {
         let del_cmd = FrontendCommand::delete_message(msg_id);
         let keyboard_command = FrontendCommand::keyboard(KeyboardCommand {
             action_type: KeyboardCommandType::Minute,
-            text: format!("Ok, {}. Now choose minute", hour),
+            text: lang.choose_minute(hour),
+            lang,
         });
 
         ProcessResult {
@@ -409,24 +889,26 @@ impl AtTimeHour {
 }
 
 impl AtTimeHour {
-    fn process(&self, data: TextEventData) -> ProcessResult {
+    fn process(&self, data: TextEventData, db: &mut DataBase) -> ProcessResult {
+        let lang = db.get_user_language(data.uid);
         if let Ok(hour) = data.input.parse::<i32>() {
-            self.proceed_next_stage(hour, 0)
+            self.proceed_next_stage(hour, 0, lang)
         } else {
             ProcessResult::msg_send(
-                "Incorrect format, expect number of hours".to_string(),
+                lang.expect_hour_number().to_string(),
                 UserState::ReadyToProcess,
             )
         }
     }
 
-    fn process_keyboard(&self, data: KeyboardEventData) -> ProcessResult {
+    fn process_keyboard(&self, data: KeyboardEventData, db: &mut DataBase) -> ProcessResult {
+        let lang = db.get_user_language(data.uid);
         if data.callback_data.starts_with("time_hour:") {
             let hour = data.callback_data[10..].parse::<i32>().unwrap();
-            self.proceed_next_stage(hour, data.msg_id)
+            self.proceed_next_stage(hour, data.msg_id, lang)
         } else {
             ProcessResult::msg_send(
-                "Incorrect keyboard format".to_string(),
+                lang.incorrect_keyboard().to_string(),
                 UserState::ReadyToProcess,
             )
         }
@@ -442,19 +924,27 @@ impl AtTimeMinute {
         db: &mut DataBase,
         now: DateTime<Utc>,
     ) -> Result<ProcessResult> {
+        let lang = db.get_user_language(uid);
         if let Some(text) = self.ev_text.as_ref().cloned() {
             // Make result command
             let result_command = format!(
-                "{}-{}-{} at {}.{} {}",
-                self.day, self.month, self.year, self.hour, minute, text
+                "{}-{} at {}.{} {}",
+                db.get_user_date_order(uid)
+                    .format(self.day as u32, self.month as u32),
+                self.year,
+                self.hour,
+                minute,
+                text
             );
 
-            let ret_text = process_text_command(uid, &result_command, now, db)
-                .ok_or(anyhow!("expected time format spec"))?;
+            let ret_text = match process_text_command(uid, &result_command, now, db) {
+                Ok(ret_text) => ret_text,
+                Err(e) => lang.parse_error(&e),
+            };
             Ok(ProcessResult::msg_send(ret_text, UserState::ReadyToProcess))
         } else {
             let send_command = FrontendCommand::send(SendMessageCommand {
-                text: "Now write event message".to_owned(),
+                text: lang.write_event_text().to_owned(),
             });
             let delete_command = FrontendCommand::delete_message(msg_id);
             Ok(ProcessResult {
@@ -482,7 +972,9 @@ impl AtTimeMinute {
             self.proceed_next_stage(minute, data.uid, data.msg_id, db, now)
         } else {
             Ok(ProcessResult::msg_send(
-                "Incorrect format, expect number of minute".to_string(),
+                db.get_user_language(data.uid)
+                    .expect_minute_number()
+                    .to_string(),
                 UserState::ReadyToProcess,
             ))
         }
@@ -499,7 +991,9 @@ impl AtTimeMinute {
             self.proceed_next_stage(minute, data.uid, data.msg_id, db, now)
         } else {
             Ok(ProcessResult::msg_send(
-                "Incorrect keyboard format".to_string(),
+                db.get_user_language(data.uid)
+                    .incorrect_keyboard()
+                    .to_string(),
                 UserState::ReadyToProcess,
             ))
         }
@@ -514,12 +1008,20 @@ impl AtTimeText {
         db: &mut DataBase,
     ) -> Result<ProcessResult> {
         let result_command = format!(
-            "{}-{}-{} at {}.{} {}",
-            self.day, self.month, self.year, self.hour, self.minute, &data.input
+            "{}-{} at {}.{} {}",
+            db.get_user_date_order(data.uid)
+                .format(self.day as u32, self.month as u32),
+            self.year,
+            self.hour,
+            self.minute,
+            &data.input
         );
 
-        let ret_text = process_text_command(data.uid, &result_command, now, db)
-            .ok_or(anyhow!("expect time spec format"))?;
+        let lang = db.get_user_language(data.uid);
+        let ret_text = match process_text_command(data.uid, &result_command, now, db) {
+            Ok(ret_text) => ret_text,
+            Err(e) => lang.parse_error(&e),
+        };
         Ok(ProcessResult::msg_send(ret_text, UserState::ReadyToProcess))
     }
 
@@ -539,13 +1041,14 @@ impl AfterInput {
         now: DateTime<Utc>,
         db: &mut DataBase,
     ) -> Result<ProcessResult> {
+        let lang = db.get_user_language(data.uid);
         let message = data.input + " " + &self.ev_text;
-        let ret_text = process_text_command(data.uid, &message, now, db).ok_or(anyhow!(
-            "expected duration formatted string, abort operation"
-        ))?;
+        let ret_text = process_text_command(data.uid, &message, now, db)
+            .map_err(|e| anyhow!(lang.parse_error(&e)))
+            .context(lang.expect_duration_spec())?;
 
         Ok(ProcessResult::msg_send(
-            format!("Resulting command:\n{message}\n{ret_text}"),
+            lang.resulting_command(&message, &ret_text),
             UserState::ReadyToProcess,
         ))
     }
@@ -553,30 +1056,463 @@ impl AfterInput {
 
 impl RepDeleteChoose {
     fn process(&self, data: TextEventData, db: &mut DataBase) -> ProcessResult {
-        let ev_to_del: i32 = match data.input.parse::<i32>() {
-            // FIXME: ev - 1 ?
+        let lang = db.get_user_language(data.uid);
+        let ev_to_del: usize = match data.input.trim().parse::<usize>() {
             Ok(ev) => ev,
             Err(_) => {
                 return ProcessResult::msg_send(
-                    "You should write number. Operation aborted.".to_string(),
+                    lang.expect_number().to_string(),
                     UserState::ReadyToProcess,
                 );
             }
         };
-        if ev_to_del < 0 || ev_to_del >= self.list_id.len() as i32 {
+        if ev_to_del < 1 || ev_to_del > self.list_id.len() {
             return ProcessResult::msg_send(
-                "Number is out of limit. Operation aborted.".to_string(),
+                lang.number_out_of_limit().to_string(),
                 UserState::ReadyToProcess,
             );
         }
-        db.delete_rep_event(ev_to_del as i64);
-        ProcessResult::msg_send("Done.".to_string(), UserState::ReadyToProcess)
+        db.delete_rep_event(self.list_id[ev_to_del - 1]);
+        ProcessResult::msg_send(lang.done().to_string(), UserState::ReadyToProcess)
     }
-    fn process_keyboard(&self) -> ProcessResult {
+    fn process_keyboard(&self, data: KeyboardEventData, db: &mut DataBase) -> ProcessResult {
         error!("State RepDeleteChoose: process_keyboard function called");
         ProcessResult::msg_send(
-            "Internal logic failed".to_string(),
+            db.get_user_language(data.uid).internal_error().to_string(),
             UserState::ReadyToProcess,
         )
     }
 }
+
+impl TimezoneChoose {
+    fn process(
+        &self,
+        data: TextEventData,
+        now: DateTime<Utc>,
+        db: &mut DataBase,
+    ) -> Result<ProcessResult> {
+        change_timezone(data.uid, &data.input, now, db)
+    }
+
+    fn process_keyboard(
+        &self,
+        data: KeyboardEventData,
+        now: DateTime<Utc>,
+        db: &mut DataBase,
+    ) -> Result<ProcessResult> {
+        if data.callback_data.starts_with("tz:") {
+            ready_process_keyboard(data, now, db)
+        } else {
+            Ok(ProcessResult::msg_send(
+                db.get_user_language(data.uid)
+                    .incorrect_keyboard()
+                    .to_string(),
+                UserState::ReadyToProcess,
+            ))
+        }
+    }
+}
+
+impl FuzzyConfirm {
+    fn process_keyboard(
+        &self,
+        data: KeyboardEventData,
+        now: DateTime<Utc>,
+        db: &mut DataBase,
+    ) -> Result<ProcessResult> {
+        let lang = db.get_user_language(data.uid);
+        let reply = match data.callback_data.as_str() {
+            // parse again, relative times count from the confirmation
+            "fuzzy:confirm" => match process_text_command(data.uid, &self.line, now, db) {
+                Ok(ret_text) => lang.resulting_command(&self.line, &ret_text),
+                Err(e) => lang.parse_error(&e),
+            },
+            "fuzzy:edit" => lang.fuzzy_edit(&self.line),
+            _ => return ready_process_keyboard(data, now, db),
+        };
+        Ok(ProcessResult {
+            frontend_command: vec![
+                FrontendCommand::delete_keyboard(data.msg_id),
+                FrontendCommand::send(SendMessageCommand { text: reply }),
+            ],
+            next_state: Some(UserState::ReadyToProcess),
+        })
+    }
+}
+
+// snooze buttons under a fired reminder, label and minutes
+pub(crate) const SNOOZE_BUTTONS: &[(&str, i64)] = &[("10m", 10), ("1h", 60)];
+
+/// Buttons under a fired reminder, `fired:<id>:<action>`. The reminder row
+/// is moved to the new time, so snoozing never makes a new event. A button
+/// ends whatever dialog was going on, e.g. waiting for a snooze time.
+pub fn process_fired_keyboard(
+    data: KeyboardEventData,
+    now: DateTime<Utc>,
+    db: &mut DataBase,
+) -> Result<ProcessResult> {
+    let (id, action) = data
+        .callback_data
+        .strip_prefix("fired:")
+        .and_then(|rest| rest.split_once(':'))
+        .context("incorrect fired event button")?;
+    let id: i64 = id.parse().context("incorrect fired event id")?;
+    let lang = db.get_user_language(data.uid);
+    let Some(event) = db.get_fired_event(id, data.uid) else {
+        return Ok(fired_event_gone(data.msg_id, lang));
+    };
+
+    let tz = db.get_user_timezone(data.uid);
+    let time = match action {
+        "done" => {
+            db.delete_fired_event(id, data.uid);
+            return Ok(ProcessResult::single(
+                FrontendCommand::edit_message(EditMessageCommand {
+                    msg_id: data.msg_id,
+                    text: format!("{}\n\n{}", data.msg_text, lang.fired_done()),
+                }),
+                Some(UserState::ReadyToProcess),
+            ));
+        }
+        "nag" => {
+            if !db.start_nag(id, data.uid, now) {
+                return Ok(fired_event_gone(data.msg_id, lang));
+            }
+            let nag = db.get_user_nag(data.uid);
+            return Ok(ProcessResult::single(
+                FrontendCommand::edit_message(EditMessageCommand {
+                    msg_id: data.msg_id,
+                    text: format!(
+                        "{}\n\n{}",
+                        data.msg_text,
+                        lang.nag_note(nag.interval_minutes, nag.max_repeats)
+                    ),
+                }),
+                Some(UserState::ReadyToProcess),
+            ));
+        }
+        "custom" => {
+            return Ok(ProcessResult::msg_send(
+                lang.expect_snooze_time().to_owned(),
+                UserState::SnoozeInput(SnoozeInput {
+                    id,
+                    msg_id: data.msg_id,
+                    msg_text: data.msg_text,
+                }),
+            ));
+        }
+        // same time of day as the reminder was set to
+        "tomorrow" => {
+            let tomorrow = now.with_timezone(&tz).date_naive() + chrono::Duration::days(1);
+            let time = event.event_time.with_timezone(&tz).time();
+            local_to_utc(&tz, tomorrow.and_time(time))
+        }
+        action => {
+            let (_, minutes) = SNOOZE_BUTTONS
+                .iter()
+                .find(|(name, _)| *name == action)
+                .context("unknown snooze button")?;
+            now + chrono::Duration::minutes(*minutes)
+        }
+    };
+    Ok(snooze_fired_event(
+        data.uid,
+        id,
+        data.msg_id,
+        &data.msg_text,
+        time,
+        db,
+    ))
+}
+
+fn snooze_fired_event(
+    uid: i64,
+    id: i64,
+    msg_id: i32,
+    msg_text: &str,
+    time: DateTime<Utc>,
+    db: &mut DataBase,
+) -> ProcessResult {
+    let lang = db.get_user_language(uid);
+    if !db.snooze_event(id, uid, time) {
+        return fired_event_gone(msg_id, lang);
+    }
+    let tz = db.get_user_timezone(uid);
+    let order = db.get_user_date_order(uid);
+    let until = lang.short_datetime(time.with_timezone(&tz).naive_local(), order);
+    ProcessResult::single(
+        FrontendCommand::edit_message(EditMessageCommand {
+            msg_id,
+            text: format!("{msg_text}\n\n{}", lang.snoozed_until(&until)),
+        }),
+        Some(UserState::ReadyToProcess),
+    )
+}
+
+fn fired_event_gone(msg_id: i32, lang: Lang) -> ProcessResult {
+    ProcessResult {
+        frontend_command: vec![
+            FrontendCommand::delete_keyboard(msg_id),
+            FrontendCommand::send(SendMessageCommand {
+                text: lang.fired_event_gone().to_owned(),
+            }),
+        ],
+        next_state: Some(UserState::ReadyToProcess),
+    }
+}
+
+impl SnoozeInput {
+    /// The time is written like in a command, `30m`, `at 18`, `tomorrow at 9`,
+    /// a `/command` leaves the reminder as it is.
+    fn process(
+        &self,
+        data: TextEventData,
+        now: DateTime<Utc>,
+        db: &mut DataBase,
+    ) -> Result<ProcessResult> {
+        if data.input.starts_with('/') {
+            return ready_process(data, now, db);
+        }
+        let lang = db.get_user_language(data.uid);
+        if db.get_fired_event(self.id, data.uid).is_none() {
+            return Ok(ProcessResult::msg_send(
+                lang.fired_event_gone().to_owned(),
+                UserState::ReadyToProcess,
+            ));
+        }
+        let tz = db.get_user_timezone(data.uid);
+        let order = db.get_user_date_order(data.uid);
+        // ask again, the fired reminder keeps waiting for a time
+        let time = match parse_time_only(&data.input, now, tz, order) {
+            Ok(Command::OneTimeEvent(ev)) => ev.event_time,
+            Ok(Command::RepetitiveEvent(_)) | Err(ParseError::NoMatch) => {
+                return Ok(ProcessResult::msg_send(
+                    lang.expect_snooze_time().to_owned(),
+                    UserState::SnoozeInput(self.clone()),
+                ));
+            }
+            Err(e) => {
+                return Ok(ProcessResult::msg_send(
+                    lang.parse_error(&e),
+                    UserState::SnoozeInput(self.clone()),
+                ));
+            }
+        };
+
+        let mut result =
+            snooze_fired_event(data.uid, self.id, self.msg_id, &self.msg_text, time, db);
+        result
+            .frontend_command
+            .push(FrontendCommand::send(SendMessageCommand {
+                text: format_return_message_header(&time, now, tz, lang, order),
+            }));
+        result.next_state = Some(UserState::ReadyToProcess);
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::OneTimeEventImpl;
+    use crate::database::tests::{db_with_user, utc};
+
+    fn text(input: &str) -> TextEventData {
+        TextEventData {
+            uid: 1,
+            msg_id: 1,
+            input: input.to_owned(),
+        }
+    }
+
+    fn button(callback_data: &str) -> KeyboardEventData {
+        KeyboardEventData {
+            uid: 1,
+            msg_id: 2,
+            callback_data: callback_data.to_owned(),
+            msg_text: String::new(),
+        }
+    }
+
+    #[test]
+    fn fuzzy_confirm_flow() {
+        let mut db = db_with_user();
+        let now = utc(5, 9, 0);
+
+        // off by default, free text gets the main keyboard
+        let res = UserState::ReadyToProcess
+            .process(text("call mom in 2 hours"), now, &mut db)
+            .unwrap();
+        assert!(matches!(res.next_state, Some(UserState::ReadyToProcess)));
+
+        let reply = |input: &str, db: &mut DataBase| {
+            let res = UserState::ReadyToProcess
+                .process(text(input), now, db)
+                .unwrap();
+            res.frontend_command
+        };
+        let send = |text: &str| {
+            vec![FrontendCommand::send(SendMessageCommand {
+                text: text.to_owned(),
+            })]
+        };
+        assert_eq!(reply("/fuzzy", &mut db), send(Lang::En.fuzzy_info(false)));
+        assert_eq!(reply("/fuzzy ON", &mut db), send(Lang::En.fuzzy_set(true)));
+        assert_eq!(reply("/fuzzy", &mut db), send(Lang::En.fuzzy_info(true)));
+        let propose = |db: &mut DataBase| {
+            let res = UserState::ReadyToProcess
+                .process(text("call mom in 2 hours"), now, db)
+                .unwrap();
+            match res.next_state {
+                Some(state @ UserState::FuzzyConfirm(_)) => state,
+                state => panic!("Unexpected state {state:?}"),
+            }
+        };
+
+        // edit leaves nothing scheduled and shows the command to fix
+        let res = propose(&mut db)
+            .process_keyboard(button("fuzzy:edit"), now, &mut db)
+            .unwrap();
+        assert!(matches!(res.next_state, Some(UserState::ReadyToProcess)));
+        assert_eq!(
+            res.frontend_command,
+            vec![
+                FrontendCommand::delete_keyboard(2),
+                FrontendCommand::send(SendMessageCommand {
+                    text: Lang::En.fuzzy_edit("2 hours call mom")
+                }),
+            ]
+        );
+        assert!(db.get_all_active_events(1).is_empty());
+
+        // confirm schedules the proposed command
+        let res = propose(&mut db)
+            .process_keyboard(button("fuzzy:confirm"), now, &mut db)
+            .unwrap();
+        assert!(matches!(res.next_state, Some(UserState::ReadyToProcess)));
+        assert_eq!(res.frontend_command[0], FrontendCommand::delete_keyboard(2));
+        match db.get_all_active_events(1).as_slice() {
+            [(Command::OneTimeEvent(ev), None, _)] => {
+                assert_eq!(ev.event_text, "call mom");
+                assert_eq!(ev.event_time, utc(5, 11, 0));
+            }
+            events => panic!("Unexpected events {events:?}"),
+        }
+    }
+
+    #[test]
+    fn snooze_input_asks_again() {
+        let mut db = db_with_user();
+        let event = OneTimeEventImpl {
+            event_text: String::from("tea"),
+            event_time: utc(5, 10, 0),
+            event_timezone: None,
+            event_leads: Vec::new(),
+        };
+        db.put(1, Command::OneTimeEvent(event), utc(5, 9, 0));
+        let id = db.extract_events_happens_already(utc(5, 10, 0))[0].id;
+        let now = utc(5, 10, 5);
+
+        let res =
+            process_fired_keyboard(button(&format!("fired:{id}:custom")), now, &mut db).unwrap();
+        let mut state = res.next_state.unwrap();
+        let expect_time = SendMessageCommand {
+            text: Lang::En.expect_snooze_time().to_owned(),
+        };
+        for (input, reply) in [
+            ("whenever", expect_time.clone()),
+            ("rep 9 1d", expect_time),
+            (
+                "at 25",
+                SendMessageCommand {
+                    text: Lang::En.parse_error(&ParseError::HourOutOfRange {
+                        hour: 25,
+                        am_pm: false,
+                    }),
+                },
+            ),
+            // only a time is taken, the text stays as it was
+            (
+                "30m tea",
+                SendMessageCommand {
+                    text: Lang::En.expect_snooze_time().to_owned(),
+                },
+            ),
+        ] {
+            let res = state.process(text(input), now, &mut db).unwrap();
+            assert_eq!(
+                res.frontend_command,
+                vec![FrontendCommand::send(reply)],
+                "{input}"
+            );
+            state = res.next_state.unwrap();
+            assert!(matches!(state, UserState::SnoozeInput(_)), "{input}");
+        }
+
+        let res = state.process(text("30m"), now, &mut db).unwrap();
+        assert!(matches!(res.next_state, Some(UserState::ReadyToProcess)));
+        assert_eq!(db.get_nearest_wakeup(), Some(utc(5, 10, 35)));
+    }
+
+    #[test]
+    fn snooze_input_lets_the_user_out() {
+        let mut db = db_with_user();
+        let event = OneTimeEventImpl {
+            event_text: String::from("tea"),
+            event_time: utc(5, 10, 0),
+            event_timezone: None,
+            event_leads: Vec::new(),
+        };
+        db.put(1, Command::OneTimeEvent(event), utc(5, 9, 0));
+        let id = db.extract_events_happens_already(utc(5, 10, 0))[0].id;
+        let now = utc(5, 10, 5);
+        let custom = |db: &mut DataBase| {
+            process_fired_keyboard(button(&format!("fired:{id}:custom")), now, db)
+                .unwrap()
+                .next_state
+                .unwrap()
+        };
+
+        // a command is run, the reminder still waits for a button
+        let res = custom(&mut db)
+            .process(text("/list"), now, &mut db)
+            .unwrap();
+        assert!(matches!(res.next_state, Some(UserState::ReadyToProcess)));
+        assert!(db.get_fired_event(id, 1).is_some());
+
+        // another button ends the dialog, the next message is a new reminder
+        custom(&mut db);
+        let res =
+            process_fired_keyboard(button(&format!("fired:{id}:done")), now, &mut db).unwrap();
+        let state = res.next_state.unwrap();
+        assert!(matches!(state, UserState::ReadyToProcess));
+        state.process(text("at 18 dentist"), now, &mut db).unwrap();
+        assert_eq!(db.get_all_active_events(1).len(), 1);
+    }
+
+    #[test]
+    fn command_cancels_edit() {
+        let mut db = db_with_user();
+        let now = utc(5, 8, 0);
+        let event = OneTimeEventImpl {
+            event_text: String::from("tea"),
+            event_time: utc(5, 10, 0),
+            event_timezone: None,
+            event_leads: Vec::new(),
+        };
+        db.put(1, Command::OneTimeEvent(event.clone()), now);
+
+        let state = UserState::ReadyToProcess
+            .process(text("/edit 1"), now, &mut db)
+            .unwrap()
+            .next_state
+            .unwrap();
+        assert!(matches!(state, UserState::EditInput(_)));
+        let res = state.process(text("/help"), now, &mut db).unwrap();
+        assert!(matches!(res.next_state, Some(UserState::ReadyToProcess)));
+        assert_eq!(
+            db.get_all_active_events(1),
+            vec![(Command::OneTimeEvent(event), None, 1)]
+        );
+    }
+}
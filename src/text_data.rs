@@ -1,15 +1,21 @@
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Weekday};
+
+use crate::command::{DateOrder, ParseError};
+
 pub(crate) const MAIN_HELP_MESSAGE_RU: &str = r"
 Remu - бот для напоминания о ваших событиях. Событие - это просто текст, который Remu напишет вам в заданное время.
-Самый простой способ начать работу - напишите что-нибудь. 
+Самый простой способ начать работу - напишите что-нибудь. Если в тексте есть время, например, «встреча завтра в 15:00», Remu предложит напоминание, его можно подтвердить или изменить (включается командой /fuzzy on).
 
 На появившейся клавиатуре можно выбрать через сколько вам напомнить (кнопки с числами), или когда (кнопка at). По кнопке at можно выбрать день и время события (с точностью до 15 мин.). Чтобы не набирать, можно сделать форвард сообщения (но только одного), или передать голосовое сообщение. Для распознавания используется speechtool от Яндекса. 
 
-Посмотреть активные события можно по команде /list. 
+Посмотреть активные события можно по команде /list. Кнопки под напоминанием отмечают его выполненным или откладывают: +10м, +1ч, на завтра или на любое другое время. Важные напоминания можно начать с ! (например, !в 9 таблетки), они будут повторяться, пока вы не нажмете Готово, см. /nag. Чтобы получить напоминание заранее, добавьте после времени, за сколько: в 14 -1ч -15м планерка. Чтобы не получать напоминания ночью, включите тихие часы командой /quiet. Список напоминаний на день каждое утро: /digest. Изменить время или текст напоминания можно кнопками под /list или командой /edit.
+
+Время событий считается в вашем часовом поясе. Посмотреть и изменить его можно командой /timezone, или просто отправьте боту свою геопозицию. Язык интерфейса меняется командой /language.
 
 Если вы хотите задать событие точнее или научится устанавливать повторяющиеся события, напишите /help more
 ";
 
-pub(crate) const DETAILED_HELP_MESSAGE_RU: &str = r"
+pub(crate) const DETAILED_HELP_MESSAGE_RU: &str = r#"
 Хотите узнать о событиях более подробно? Хорошо!
 
 В данный момент есть 2 типа событий: единичные и повторяющиеся. 
@@ -19,9 +25,18 @@ pub(crate) const DETAILED_HELP_MESSAGE_RU: &str = r"
 Пример для *во* сколько:
 ```
 10-11 at 12.30 ололо - 10 ноября в 12.30
-10 at 11 траляля - 10 числа этого месяца в 11.00
+10 at 11 траляля - 10 числа этого месяца в 11.00 (порядок ММ-ДД: /date_order)
+10 ноября в 12 обед  - месяц можно написать словом (10 nov at 12 тоже)
+в 7 вечера ужин      - утра, дня и вечера тоже понимаются
 в 9.35 трюлюлю  - сегодня в 9.35
 в 22 ohaha - сегодня в 10 вечера
+завтра в 9 позвонить - завтра в 9.00 (today, tomorrow, сегодня, послезавтра тоже можно)
+в пятницу в 18 бар   - в ближайшую пятницу в 18.00
+friday at 18 bar     - то же самое
+at 7:30 pm dinner    - 12-часовой формат (am/pm)
+2026-11-03T14:00 demo        - формат ISO-8601, в вашем часовом поясе
+2026-11-03T14:00+02:00 demo  - или с явным смещением
+15-11 в 9 Europe/London регистрация - в другом поясе (UTC, PST, +05:30 тоже), и в rep 9 UTC 1d ...
 ```
 Пример для *через* сколько:
 ```
@@ -29,27 +44,1395 @@ pub(crate) const DETAILED_HELP_MESSAGE_RU: &str = r"
 1д2ч3м4с текст2 - тоже, но на русском
 2ч30м text3     - 2 часа 30 мин.
 1c text4        - 1 секунда
+2h 30m text5    - можно через пробел
+1 день 3 часа text6 - или словами
+1w text7        - неделя (w, н), месяц (mo, мес) и год (y, г) тоже есть
 ```
+Несколько напоминаний можно отправить одним сообщением, каждое на своей строке:
+```
+9 at 10 стоматолог
+12 at 15 позвонить в банк
+```
+В ответ придет сводка: какие строки запланированы, а какие нет и почему.
+
+Проверить, как понимается строка, не сохраняя ее, можно командой /parse, например, /parse rep 9 1d чай.
+
 Ну и более формально, синтаксис для первого случая: 
 <день>-<месяц> [at|в] <час>.<минута> <ваш текст события>. 
 Обязательным здесь является частица at(или в), час и текст события.
 
 Синтаксис для второго случая
 <>d<>h<>m<>s <текст события>
-где в <> должно стоять число дней(d), часов(h), минут(m), секунд(s). Русскими буквами (д, ч, м, с) тоже пойдет. Достаточно чтобы было заполнено хотя бы одно поле.
+где в <> должно стоять число дней(d), часов(h), минут(m), секунд(s). Русскими буквами (д, ч, м, с) тоже пойдет. Достаточно чтобы было заполнено хотя бы одно поле. Еще есть недели (w, н), месяцы (mo, мес) и годы (y, г), месяц прибавляется по календарю: через месяц после 31 января будет 28 февраля.
 
 *Повторяющиеся события* - это события у которых есть начальное время и время, через которое они должны повторятся. Искользуется синтаксис единичных событий. Пример:
 ```
 rep 23-12 11.30 7d позвони маме
 ```
 Данное событие будет каждую неделю в 11.30, начиная с 23 декабря напоминать вам позвонить маме. 
+Вместо rep можно писать repeat или повтор.
+
+Вместо периода можно указать календарное правило:
+```
+rep 01-11 10.00 1mo заплатить за квартиру - 1 числа каждого месяца
+rep 12-03 9 1y день рождения              - каждый год 12 марта
+rep 10.00 last зарплата                   - в последний день месяца
+rep 19.00 2tue митап                      - во второй вторник месяца
+rep 18 посл-пт созвон                     - в последнюю пятницу месяца
+```
+Если в месяце нет нужного дня (например, 31-го), событие будет в последний день месяца.
+
+Можно выбрать дни недели:
+```
+every mon,wed,fri at 8.30 standup - по понедельникам, средам и пятницам
+каждый пн,ср,пт в 8.30 зарядка    - то же самое
+каждую субботу в 10 уборка
+every weekday at 9 work           - по будним дням (weekend, будни, выходные тоже можно)
+rep 8.30 tue,thu gym              - то же через rep
+```
+
+Для сложных расписаний подойдет cron-выражение из 5 полей (минута, час, день месяца, месяц, день недели), время берется в вашем часовом поясе:
+```
+cron "0 9 * * 1-5" планерка     - по будням в 9.00
+cron "*/30 10-18 * * *" размяться - каждые полчаса с 10 до 18
+```
+
+Перед текстом повторяющегося события можно указать, когда серия закончится: дату (until или до) и/или число напоминаний (x):
+```
+rep 9 1d до 31-12 x10 витамины - каждый день до 31 декабря, но не больше 10 раз
+every fri at 18 until 20-12 отчет
+```
+
+"#;
+
+pub(crate) const MAIN_HELP_MESSAGE_EN: &str = r"
+Remu is a bot that reminds you of your events. An event is just a text Remu will send you at the given time.
+The easiest way to start is to write something. If the text mentions a time, e.g. call mom in 2 hours, Remu offers a reminder to confirm or edit (turned on by /fuzzy on).
+
+On the keyboard below the message choose when to remind you: in some time (buttons with numbers) or at a given moment (the at button). The at button lets you pick the day and time of the event (with 15 min. precision). Instead of typing you can forward a message (only one).
+
+Active events are shown by the /list command. The buttons under a reminder mark it done or snooze it: +10m, +1h, tomorrow or any other time. Important reminders can start with ! (e.g. !at 9 pills), they are sent again until you press Done, see /nag. To be alerted in advance, add how long before after the time: at 14 -1h -15m standup. To get no reminders at night, set quiet hours with /quiet. To get the list of the day's reminders every morning, see /digest. To change the time or text of a reminder, use the buttons under /list or the /edit command.
 
+Event times are in your time zone. See or change it with /timezone, or just send your location to the bot. The interface language is changed with /language.
+
+To set events more precisely or to learn about repetitive events, write /help more
 ";
 
+pub(crate) const DETAILED_HELP_MESSAGE_EN: &str = r#"
+Want to know more about events? Sure!
+
+There are 2 types of events: one-time and repetitive.
+
+*One-time events* are set in two ways: *AT* what time or *IN* how long the event should happen. Examples explain it best.
+
+Examples for *at*:
+```
+10-11 at 12.30 lunch  - 10 November at 12.30
+10 at 11 call         - the 10th of this month at 11.00 (MM-DD dates: /date_order)
+10 nov at 12 lunch    - month can be a word (10 ноября в 12 works too)
+at 9.35 coffee        - today at 9.35
+at 22 sleep           - today at 10 pm
+at 7:30 pm dinner     - 12-hour clock (am/pm)
+tomorrow at 9 call    - tomorrow at 9.00 (today, day after tomorrow too)
+friday at 18 bar      - the nearest Friday at 18.00
+2026-11-03T14:00 demo        - ISO-8601, in your time zone
+2026-11-03T14:00+02:00 demo  - or with an explicit offset
+15-11 at 9 Europe/London check-in - in another zone (UTC, PST, +05:30 too), rep 9 UTC 1d ... as well
+```
+Examples for *in*:
+```
+1d2h3m4s text1  - 1 day, 2 hours, 3 min., 4 sec.
+1д2ч3м4с text2  - the same in Russian
+2h30m text3     - 2 hours 30 min.
+2h 30m text4    - spaces are fine
+1 day 3 hours text5 - or words
+1w text6        - weeks (w), months (mo) and years (y) too
+```
+Several reminders can be sent in one message, one per line:
+```
+9 at 10 dentist
+12 at 15 call the bank
+```
+The reply sums up which lines were scheduled and why others were not.
+
+To check how a line is read without saving it, send /parse and the line, e.g. /parse rep 9 1d tea.
+
+More formally, the syntax of the first case is:
+<day>-<month> at <hour>.<minute> <your event text>.
+The word at, the hour and the event text are required.
+
+The syntax of the second case is:
+<>d<>h<>m<>s <event text>
+where <> is the number of days (d), hours (h), minutes (m), seconds (s). At least one field is required. There are also weeks (w), months (mo) and years (y); months are added by the calendar: a month after January 31 is February 28.
+
+*Repetitive events* have a start time and a rule for repeating. The one-time syntax is used. Example:
+```
+rep 23-12 11.30 7d call mom
+```
+This event reminds you to call mom every week at 11.30 starting from December 23. Instead of rep you can write repeat.
+
+A calendar rule can be used instead of a period:
+```
+rep 01-11 10.00 1mo pay the rent - the 1st of every month
+rep 12-03 9 1y birthday          - every year on March 12
+rep 10.00 last salary            - the last day of every month
+rep 19.00 2tue meetup            - the second Tuesday of every month
+rep 18 last-fri sync             - the last Friday of every month
+```
+If a month has no such day (e.g. the 31st), the event is on the last day of the month.
+
+Weekdays can be chosen:
+```
+every mon,wed,fri at 8.30 standup - on Mondays, Wednesdays and Fridays
+every saturday at 10 cleaning
+every weekday at 9 work           - on working days (weekend too)
+rep 8.30 tue,thu gym              - the same with rep
+```
+
+Complex schedules fit a 5-field cron expression (minute, hour, day of month, month, weekday) in your time zone:
+```
+cron "0 9 * * 1-5" planning       - on working days at 9.00
+cron "*/30 10-18 * * *" stretch   - every half an hour from 10 to 18
+```
+
+Before the text of a repetitive event you can set when the series ends: a date (until) and/or a number of reminders (x):
+```
+rep 9 1d until 31-12 x10 vitamins - every day until December 31, but at most 10 times
+every fri at 18 until 20-12 report
+```
+
+"#;
+
+/// Interface language of a user. Stored by its code, new users get the one
+/// of their Telegram client.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Lang {
+    #[default]
+    En,
+    Ru,
+}
+
+pub const LANGUAGES: &[Lang] = &[Lang::En, Lang::Ru];
+
+const MONTHS_EN: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const MONTHS_RU: [&str; 12] = [
+    "Январь",
+    "Февраль",
+    "Март",
+    "Апрель",
+    "Май",
+    "Июнь",
+    "Июль",
+    "Август",
+    "Сентябрь",
+    "Октябрь",
+    "Ноябрь",
+    "Декабрь",
+];
+
+const MONTHS_RU_GENITIVE: [&str; 12] = [
+    "января",
+    "февраля",
+    "марта",
+    "апреля",
+    "мая",
+    "июня",
+    "июля",
+    "августа",
+    "сентября",
+    "октября",
+    "ноября",
+    "декабря",
+];
+
+const MONTHS_RU_SHORT: [&str; 12] = [
+    "янв", "фев", "мар", "апр", "мая", "июн", "июл", "авг", "сен", "окт", "ноя", "дек",
+];
+
+const WEEKDAYS_RU: [&str; 7] = ["пн", "вт", "ср", "чт", "пт", "сб", "вс"];
+
+/// Russian noun form for `n`: `1 день`, `2 дня`, `5 дней`.
+pub(crate) fn plural_ru(
+    n: u32,
+    one: &'static str,
+    few: &'static str,
+    many: &'static str,
+) -> &'static str {
+    match (n % 10, n % 100) {
+        (_, 11..=14) => many,
+        (1, _) => one,
+        (2..=4, _) => few,
+        _ => many,
+    }
+}
+
+impl Lang {
+    /// Telegram `language_code` (`ru`, `en-US`) or the stored code.
+    pub fn from_code(code: Option<&str>) -> Lang {
+        let code = code.unwrap_or_default().to_lowercase();
+        match code.split(['-', '_']).next() {
+            Some("ru") => Lang::Ru,
+            _ => Lang::En,
+        }
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::Ru => "ru",
+        }
+    }
+
+    /// Name of the language in itself, for the language keyboard.
+    pub fn name(self) -> &'static str {
+        match self {
+            Lang::En => "English",
+            Lang::Ru => "Русский",
+        }
+    }
+
+    // Dates ------------------------------------------------------------------
+
+    /// Month in the calendar header, `October`.
+    pub fn month_name(self, month: u32) -> &'static str {
+        let i = month as usize - 1;
+        match self {
+            Lang::En => MONTHS_EN[i],
+            Lang::Ru => MONTHS_RU[i],
+        }
+    }
+
+    pub fn weekday_name(self, day: Weekday) -> String {
+        match self {
+            Lang::En => day.to_string(),
+            Lang::Ru => WEEKDAYS_RU[day.num_days_from_monday() as usize].to_owned(),
+        }
+    }
+
+    /// `18 Oct`, English follows the user's date order, `Oct 18`.
+    pub fn short_date(self, date: NaiveDate, order: DateOrder) -> String {
+        let i = date.month0() as usize;
+        match (self, order) {
+            (Lang::En, DateOrder::DayMonth) => format!("{} {}", date.day(), &MONTHS_EN[i][..3]),
+            (Lang::En, DateOrder::MonthDay) => format!("{} {}", &MONTHS_EN[i][..3], date.day()),
+            (Lang::Ru, _) => format!("{} {}", date.day(), MONTHS_RU_SHORT[i]),
+        }
+    }
+
+    /// `18 Oct 9.30`
+    pub fn short_datetime(self, time: NaiveDateTime, order: DateOrder) -> String {
+        format!(
+            "{} {}",
+            self.short_date(time.date(), order),
+            time.format("%k.%M").to_string().trim()
+        )
+    }
+
+    /// `18 October` or `October 18`
+    pub fn long_date(self, date: NaiveDate, order: DateOrder) -> String {
+        let i = date.month0() as usize;
+        match (self, order) {
+            (Lang::En, DateOrder::DayMonth) => format!("{} {}", date.day(), MONTHS_EN[i]),
+            (Lang::En, DateOrder::MonthDay) => format!("{} {}", MONTHS_EN[i], date.day()),
+            (Lang::Ru, _) => format!("{} {}", date.day(), MONTHS_RU_GENITIVE[i]),
+        }
+    }
+
+    /// How numeric dates are written, `DD-MM`.
+    pub fn date_pattern(self, order: DateOrder) -> &'static str {
+        match (self, order) {
+            (Lang::En, DateOrder::DayMonth) => "DD-MM",
+            (Lang::En, DateOrder::MonthDay) => "MM-DD",
+            (Lang::Ru, DateOrder::DayMonth) => "ДД-ММ",
+            (Lang::Ru, DateOrder::MonthDay) => "ММ-ДД",
+        }
+    }
+
+    // Help -------------------------------------------------------------------
+
+    pub fn main_help(self) -> &'static str {
+        match self {
+            Lang::En => MAIN_HELP_MESSAGE_EN,
+            Lang::Ru => MAIN_HELP_MESSAGE_RU,
+        }
+    }
+
+    pub fn detailed_help(self) -> &'static str {
+        match self {
+            Lang::En => DETAILED_HELP_MESSAGE_EN,
+            Lang::Ru => DETAILED_HELP_MESSAGE_RU,
+        }
+    }
+
+    // Buttons ----------------------------------------------------------------
+
+    pub fn button_at(self) -> &'static str {
+        match self {
+            Lang::En => "at",
+            Lang::Ru => "в",
+        }
+    }
+
+    pub fn button_after(self) -> &'static str {
+        match self {
+            Lang::En => "after",
+            Lang::Ru => "через",
+        }
+    }
+
+    pub fn button_ok(self) -> &'static str {
+        match self {
+            Lang::En => "Ok",
+            Lang::Ru => "Ок",
+        }
+    }
+
+    /// Label of a duration button like `5m`.
+    pub fn button_duration(self, duration: &str) -> String {
+        match self {
+            Lang::En => duration.to_owned(),
+            Lang::Ru => duration
+                .replace('m', "м")
+                .replace('h', "ч")
+                .replace('d', "д"),
+        }
+    }
+
+    pub fn button_yes(self) -> &'static str {
+        match self {
+            Lang::En => "Yes",
+            Lang::Ru => "Да",
+        }
+    }
+
+    pub fn button_no(self) -> &'static str {
+        match self {
+            Lang::En => "No",
+            Lang::Ru => "Нет",
+        }
+    }
+
+    pub fn button_confirm(self) -> &'static str {
+        match self {
+            Lang::En => "Confirm",
+            Lang::Ru => "Подтвердить",
+        }
+    }
+
+    pub fn button_edit(self) -> &'static str {
+        match self {
+            Lang::En => "Edit",
+            Lang::Ru => "Изменить",
+        }
+    }
+
+    pub fn button_done(self) -> &'static str {
+        match self {
+            Lang::En => "Done",
+            Lang::Ru => "Готово",
+        }
+    }
+
+    pub fn button_nag(self) -> &'static str {
+        match self {
+            Lang::En => "Keep reminding",
+            Lang::Ru => "Напоминать",
+        }
+    }
+
+    pub fn button_later(self) -> &'static str {
+        match self {
+            Lang::En => "Later...",
+            Lang::Ru => "Позже...",
+        }
+    }
+
+    pub fn button_today(self) -> &'static str {
+        match self {
+            Lang::En => "today",
+            Lang::Ru => "сегодня",
+        }
+    }
+
+    pub fn button_tomorrow(self) -> &'static str {
+        match self {
+            Lang::En => "tomorrow",
+            Lang::Ru => "завтра",
+        }
+    }
+
+    /// City of a time zone button, `Europe/New_York` -> `New York`.
+    pub fn city(self, tz_name: &str) -> String {
+        let ru = match tz_name {
+            "Europe/London" => "Лондон",
+            "Europe/Berlin" => "Берлин",
+            "Europe/Moscow" => "Москва",
+            "Asia/Dubai" => "Дубай",
+            "Asia/Kolkata" => "Калькутта",
+            "Asia/Tokyo" => "Токио",
+            "America/New_York" => "Нью-Йорк",
+            "America/Los_Angeles" => "Лос-Анджелес",
+            _ => "",
+        };
+        match self {
+            Lang::Ru if !ru.is_empty() => ru.to_owned(),
+            _ => tz_name
+                .rsplit('/')
+                .next()
+                .unwrap_or(tz_name)
+                .replace('_', " "),
+        }
+    }
+
+    // Dialogs ----------------------------------------------------------------
+
+    pub fn expect_duration(self) -> &'static str {
+        match self {
+            Lang::En => "Ok, now write time duration.",
+            Lang::Ru => "Хорошо, теперь напишите, через сколько напомнить.",
+        }
+    }
+
+    pub fn expect_time(self) -> &'static str {
+        match self {
+            Lang::En => "Ok, now write the time of event",
+            Lang::Ru => "Хорошо, теперь выберите время события",
+        }
+    }
+
+    pub fn choose_date(self) -> &'static str {
+        match self {
+            Lang::En => "Ok, now choose date",
+            Lang::Ru => "Хорошо, теперь выберите дату",
+        }
+    }
+
+    pub fn choose_minute(self, hour: i32) -> String {
+        match self {
+            Lang::En => format!("Ok, {hour}. Now choose minute"),
+            Lang::Ru => format!("Хорошо, {hour} ч. Теперь выберите минуты"),
+        }
+    }
+
+    pub fn write_event_text(self) -> &'static str {
+        match self {
+            Lang::En => "Now write event message",
+            Lang::Ru => "Теперь напишите текст события",
+        }
+    }
+
+    pub fn expect_hour_number(self) -> &'static str {
+        match self {
+            Lang::En => "Incorrect format, expect number of hours",
+            Lang::Ru => "Неверный формат, ожидается число часов",
+        }
+    }
+
+    pub fn expect_minute_number(self) -> &'static str {
+        match self {
+            Lang::En => "Incorrect format, expect number of minute",
+            Lang::Ru => "Неверный формат, ожидается число минут",
+        }
+    }
+
+    pub fn incorrect_keyboard(self) -> &'static str {
+        match self {
+            Lang::En => "Incorrect keyboard format",
+            Lang::Ru => "Неизвестная кнопка",
+        }
+    }
+
+    pub fn internal_error(self) -> &'static str {
+        match self {
+            Lang::En => "Internal logic failed",
+            Lang::Ru => "Внутренняя ошибка",
+        }
+    }
+
+    pub fn resulting_command(self, command: &str, result: &str) -> String {
+        match self {
+            Lang::En => format!("Resulting command:\n{command}\n{result}"),
+            Lang::Ru => format!("Итоговая команда:\n{command}\n{result}"),
+        }
+    }
+
+    pub fn unknown_command(self, command: &str) -> String {
+        match self {
+            Lang::En => format!("Unknown command: {command}"),
+            Lang::Ru => format!("Неизвестная команда: {command}"),
+        }
+    }
+
+    pub fn expect_duration_spec(self) -> &'static str {
+        match self {
+            Lang::En => "expected duration formatted string, abort operation",
+            Lang::Ru => "ожидается длительность, операция отменена",
+        }
+    }
+
+    pub fn processing_error(self, error: &str) -> String {
+        match self {
+            Lang::En => format!("Error while state machine processing:\n\n{error}"),
+            Lang::Ru => format!("Ошибка при обработке сообщения:\n\n{error}"),
+        }
+    }
+
+    pub fn keyboard_error(self, error: &str) -> String {
+        match self {
+            Lang::En => {
+                format!("error while processing keyboard, return to default state: {error}")
+            }
+            Lang::Ru => format!("Ошибка при обработке кнопки, возврат в начало: {error}"),
+        }
+    }
+
+    pub fn start_error(self, error: &str) -> String {
+        match self {
+            Lang::En => format!("cannot process message: {error}"),
+            Lang::Ru => format!("не удалось обработать сообщение: {error}"),
+        }
+    }
+
+    // Repetitive events ------------------------------------------------------
+
+    pub fn no_rep_events(self) -> &'static str {
+        match self {
+            Lang::En => "No current rep event",
+            Lang::Ru => "Нет повторяющихся событий",
+        }
+    }
+
+    pub fn rep_delete_header(self) -> &'static str {
+        match self {
+            Lang::En => "Here is your rep events list. Write the number of one to delete:\n",
+            Lang::Ru => "Ваши повторяющиеся события. Напишите номер того, которое нужно удалить:\n",
+        }
+    }
+
+    pub fn expect_number(self) -> &'static str {
+        match self {
+            Lang::En => "You should write number. Operation aborted.",
+            Lang::Ru => "Нужно написать число. Операция отменена.",
+        }
+    }
+
+    pub fn number_out_of_limit(self) -> &'static str {
+        match self {
+            Lang::En => "Number is out of limit. Operation aborted.",
+            Lang::Ru => "Нет события с таким номером. Операция отменена.",
+        }
+    }
+
+    pub fn done(self) -> &'static str {
+        match self {
+            Lang::En => "Done.",
+            Lang::Ru => "Готово.",
+        }
+    }
+
+    // Time zone --------------------------------------------------------------
+
+    pub fn timezone_info(self, timezone: &str, local_time: &str) -> String {
+        match self {
+            Lang::En => format!(
+                "Your time zone is {timezone}, local time {local_time}.\nOk, now choose a new one, or write its name (e.g. Europe/Berlin) or UTC offset (e.g. +05:30). You can also send your location."
+            ),
+            Lang::Ru => format!(
+                "Ваш часовой пояс {timezone}, местное время {local_time}.\nВыберите новый, или напишите его название (например, Europe/Berlin) или смещение от UTC (например, +05:30). Можно также отправить свою геопозицию."
+            ),
+        }
+    }
+
+    pub fn unknown_timezone(self, input: &str) -> String {
+        match self {
+            Lang::En => format!("Unknown time zone: {input}. Operation aborted."),
+            Lang::Ru => format!("Неизвестный часовой пояс: {input}. Операция отменена."),
+        }
+    }
+
+    pub fn timezone_set(self, timezone: &str, events: &str) -> String {
+        match self {
+            Lang::En => format!("Time zone set to {timezone}.\n\nYour reminders:\n{events}"),
+            Lang::Ru => {
+                format!("Часовой пояс изменен на {timezone}.\n\nВаши напоминания:\n{events}")
+            }
+        }
+    }
+
+    pub fn timezone_at_location(self, timezone: &str) -> String {
+        match self {
+            Lang::En => format!("Time zone at your location is {timezone}. Use it?"),
+            Lang::Ru => format!("Часовой пояс в вашей точке: {timezone}. Использовать его?"),
+        }
+    }
+
+    pub fn unknown_location_timezone(self) -> &'static str {
+        match self {
+            Lang::En => "Cannot find time zone for this location, try /timezone",
+            Lang::Ru => "Не удалось определить часовой пояс для этой точки, попробуйте /timezone",
+        }
+    }
+
+    // Language ---------------------------------------------------------------
+
+    pub fn language_prompt(self) -> String {
+        match self {
+            Lang::En => format!("Interface language: {}. Choose a new one:", self.name()),
+            Lang::Ru => format!("Язык интерфейса: {}. Выберите новый:", self.name()),
+        }
+    }
+
+    pub fn language_set(self) -> String {
+        match self {
+            Lang::En => format!("Interface language set to {}.", self.name()),
+            Lang::Ru => format!("Язык интерфейса изменен на {}.", self.name()),
+        }
+    }
+
+    pub fn unknown_language(self, input: &str) -> String {
+        let codes: Vec<&str> = LANGUAGES.iter().map(|lang| lang.code()).collect();
+        match self {
+            Lang::En => format!(
+                "Unknown language: {input}. Available: {}.",
+                codes.join(", ")
+            ),
+            Lang::Ru => format!("Неизвестный язык: {input}. Доступны: {}.", codes.join(", ")),
+        }
+    }
+
+    // Date order -------------------------------------------------------------
+
+    /// Button of the date order keyboard, shows New Year's Eve in that order.
+    pub fn date_order_example(self, order: DateOrder) -> String {
+        format!("{} ({})", order.format(31, 12), self.date_pattern(order))
+    }
+
+    pub fn date_order_prompt(self, order: DateOrder) -> String {
+        match self {
+            Lang::En => format!(
+                "Dates like 03-04 are read as {}. Choose how you write dates:",
+                self.date_pattern(order)
+            ),
+            Lang::Ru => format!(
+                "Даты вида 03-04 читаются как {}. Выберите, как вы пишете даты:",
+                self.date_pattern(order)
+            ),
+        }
+    }
+
+    pub fn date_order_set(self, order: DateOrder) -> String {
+        match self {
+            Lang::En => format!("Dates are now read as {}.", self.date_pattern(order)),
+            Lang::Ru => format!("Теперь даты читаются как {}.", self.date_pattern(order)),
+        }
+    }
+
+    pub fn unknown_date_order(self, input: &str) -> String {
+        match self {
+            Lang::En => format!("Unknown date order: {input}. Use dmy or mdy."),
+            Lang::Ru => format!("Неизвестный порядок даты: {input}. Используйте dmy или mdy."),
+        }
+    }
+
+    pub fn parse_usage(self) -> &'static str {
+        match self {
+            Lang::En => {
+                "Write a reminder after the command to see how it is read, e.g. /parse rep 9 1d tea"
+            }
+            Lang::Ru => {
+                "Напишите напоминание после команды, чтобы увидеть, как оно понимается, например, /parse rep 9 1d чай"
+            }
+        }
+    }
+
+    /// Reply to `/parse`, `rule` is `None` for one-time events.
+    pub fn parse_preview(
+        self,
+        text: &str,
+        local: &str,
+        utc: &str,
+        rule: Option<&str>,
+        next: &[String],
+    ) -> String {
+        let (header, text_label, time_label, rule_label, no_rule, next_label) = match self {
+            Lang::En => (
+                "Nothing is saved, the line reads as:",
+                "Text",
+                "Time",
+                "Repeats",
+                "no",
+                "Next reminders",
+            ),
+            Lang::Ru => (
+                "Ничего не сохранено, строка понимается так:",
+                "Текст",
+                "Время",
+                "Повтор",
+                "нет",
+                "Ближайшие напоминания",
+            ),
+        };
+        let mut result = format!(
+            "{header}\n{text_label}: {text}\n{time_label}: {local}\nUTC: {utc}\n{rule_label}: {}",
+            rule.unwrap_or(no_rule)
+        );
+        if !next.is_empty() {
+            result.push_str(&format!("\n{next_label}:"));
+            for (i, time) in next.iter().enumerate() {
+                result.push_str(&format!("\n{}) {time}", i + 1));
+            }
+        }
+        result
+    }
+
+    pub fn fired_done(self) -> &'static str {
+        match self {
+            Lang::En => "Done.",
+            Lang::Ru => "Выполнено.",
+        }
+    }
+
+    pub fn snoozed_until(self, time: &str) -> String {
+        match self {
+            Lang::En => format!("Snoozed until {time}."),
+            Lang::Ru => format!("Отложено до {time}."),
+        }
+    }
+
+    pub fn expect_snooze_time(self) -> &'static str {
+        match self {
+            Lang::En => "When to remind again? For example, 30m, at 18 or tomorrow at 9",
+            Lang::Ru => "Когда напомнить снова? Например, 30м, в 18 или завтра в 9",
+        }
+    }
+
+    /// How a persistent reminder is repeated, `every 5 min, up to 12 times`.
+    pub fn nag_note(self, interval: u32, max_repeats: u32) -> String {
+        match self {
+            Lang::En => format!(
+                "Sent again every {interval} min until you press Done, up to {max_repeats} times"
+            ),
+            Lang::Ru => format!(
+                "Будет повторяться каждые {interval} мин., пока вы не нажмете Готово, повторов: до {max_repeats}"
+            ),
+        }
+    }
+
+    pub fn nag_info(self, interval: u32, max_repeats: u32) -> String {
+        match self {
+            Lang::En => format!(
+                "Reminders starting with ! (e.g. !at 9 pills) are sent again every {interval} min until you press Done, up to {max_repeats} times.\nChange it with /nag <minutes> <times>, e.g. /nag 10 6"
+            ),
+            Lang::Ru => format!(
+                "Напоминания, начинающиеся с ! (например, !в 9 таблетки), повторяются каждые {interval} мин., пока вы не нажмете Готово, повторов: до {max_repeats}.\nИзменить: /nag <минуты> <повторы>, например, /nag 10 6"
+            ),
+        }
+    }
+
+    pub fn nag_set(self, interval: u32, max_repeats: u32) -> String {
+        match self {
+            Lang::En => format!(
+                "Persistent reminders now repeat every {interval} min, up to {max_repeats} times."
+            ),
+            Lang::Ru => format!(
+                "Теперь настойчивые напоминания повторяются каждые {interval} мин., повторов: до {max_repeats}."
+            ),
+        }
+    }
+
+    pub fn unknown_nag(self, input: &str) -> String {
+        match self {
+            Lang::En => format!(
+                "Cannot read \"{input}\", write minutes (1-1440) and times (1-100), e.g. /nag 10 6"
+            ),
+            Lang::Ru => format!(
+                "Не получилось понять \"{input}\", напишите минуты (1-1440) и число повторов (1-100), например, /nag 10 6"
+            ),
+        }
+    }
+
+    /// Current quiet hours, `None` when they are off.
+    pub fn quiet_info(self, quiet: Option<(String, String)>) -> String {
+        match (self, quiet) {
+            (Lang::En, Some((start, end))) => format!(
+                "Quiet hours: {start}-{end}. Reminders due then are sent at {end}, ones repeating more often than hourly are skipped.\nChange them with /quiet 23.00 8.00, turn off with /quiet off"
+            ),
+            (Lang::En, None) => {
+                "Quiet hours are off. Set them with /quiet 23.00 8.00 to get no reminders at night".to_owned()
+            }
+            (Lang::Ru, Some((start, end))) => format!(
+                "Тихие часы: {start}-{end}. Напоминания на это время придут в {end}, а повторяющиеся чаще раза в час будут пропущены.\nИзменить: /quiet 23.00 8.00, выключить: /quiet off"
+            ),
+            (Lang::Ru, None) => {
+                "Тихие часы выключены. Включите их, например, /quiet 23.00 8.00, чтобы не получать напоминания ночью".to_owned()
+            }
+        }
+    }
+
+    pub fn quiet_set(self, start: &str, end: &str) -> String {
+        match self {
+            Lang::En => format!("Quiet hours are set to {start}-{end}."),
+            Lang::Ru => format!("Тихие часы: {start}-{end}."),
+        }
+    }
+
+    pub fn quiet_off(self) -> &'static str {
+        match self {
+            Lang::En => "Quiet hours are off.",
+            Lang::Ru => "Тихие часы выключены.",
+        }
+    }
+
+    pub fn unknown_quiet(self, input: &str) -> String {
+        match self {
+            Lang::En => format!(
+                "Cannot read \"{input}\", write when quiet hours start and end, e.g. /quiet 23.00 8.00, or /quiet off"
+            ),
+            Lang::Ru => format!(
+                "Не получилось понять \"{input}\", напишите начало и конец тихих часов, например, /quiet 23.00 8.00, или /quiet off"
+            ),
+        }
+    }
+
+    /// Digest time, `None` when the digest is off.
+    pub fn digest_info(self, time: Option<String>) -> String {
+        match (self, time) {
+            (Lang::En, Some(time)) => format!(
+                "The list of the day's reminders is sent every day at {time}.\nChange the time with /digest 8.00, turn off with /digest off"
+            ),
+            (Lang::En, None) => {
+                "Daily digest is off. Turn it on with /digest 8.00 to get the list of the day's reminders every morning".to_owned()
+            }
+            (Lang::Ru, Some(time)) => format!(
+                "Список напоминаний на день приходит каждый день в {time}.\nИзменить время: /digest 8.00, выключить: /digest off"
+            ),
+            (Lang::Ru, None) => {
+                "Сводка на день выключена. Включите ее, например, /digest 8.00, чтобы каждое утро получать список напоминаний на день".to_owned()
+            }
+        }
+    }
+
+    pub fn digest_set(self, time: &str) -> String {
+        match self {
+            Lang::En => format!("The day's reminders will be sent every day at {time}."),
+            Lang::Ru => format!("Список напоминаний на день будет приходить каждый день в {time}."),
+        }
+    }
+
+    pub fn digest_off(self) -> &'static str {
+        match self {
+            Lang::En => "Daily digest is off.",
+            Lang::Ru => "Сводка на день выключена.",
+        }
+    }
+
+    pub fn unknown_digest(self, input: &str) -> String {
+        match self {
+            Lang::En => format!(
+                "Cannot read \"{input}\", write the time, e.g. /digest 8.00, or /digest off"
+            ),
+            Lang::Ru => format!(
+                "Не получилось понять \"{input}\", напишите время, например, /digest 8.00, или /digest off"
+            ),
+        }
+    }
+
+    pub fn digest(self, list: &str) -> String {
+        match self {
+            Lang::En => format!("Today:\n{list}"),
+            Lang::Ru => format!("Сегодня:\n{list}"),
+        }
+    }
+
+    pub fn digest_empty(self) -> &'static str {
+        match self {
+            Lang::En => "No reminders for the rest of today.",
+            Lang::Ru => "На сегодня больше напоминаний нет.",
+        }
+    }
+
+    /// A series firing several times today, `water (8 times)`.
+    pub fn digest_repeats(self, text: &str, count: usize) -> String {
+        match self {
+            Lang::En => format!("{text} ({count} times)"),
+            Lang::Ru => format!(
+                "{text} ({count} {})",
+                plural_ru(count as u32, "раз", "раза", "раз")
+            ),
+        }
+    }
+
+    pub fn fired_event_gone(self) -> &'static str {
+        match self {
+            Lang::En => "This reminder is already done, snoozed or too old.",
+            Lang::Ru => "Это напоминание уже выполнено, отложено или слишком старое.",
+        }
+    }
+
+    pub fn fuzzy_proposal(self, command: &str, preview: &str) -> String {
+        match self {
+            Lang::En => format!("Looks like a reminder:\n{command}\n{preview}"),
+            Lang::Ru => format!("Похоже на напоминание:\n{command}\n{preview}"),
+        }
+    }
+
+    pub fn fuzzy_edit(self, command: &str) -> String {
+        match self {
+            Lang::En => format!("Send the corrected reminder, for example:\n{command}"),
+            Lang::Ru => format!("Отправьте исправленное напоминание, например:\n{command}"),
+        }
+    }
+
+    pub fn fuzzy_info(self, enabled: bool) -> &'static str {
+        match (self, enabled) {
+            (Lang::En, true) => {
+                "Time phrases in free text, e.g. call mom in 2 hours, are offered as reminders.\nTurn off with /fuzzy off"
+            }
+            (Lang::En, false) => {
+                "Free text is not searched for time phrases. Turn it on with /fuzzy on to get call mom in 2 hours offered as a reminder"
+            }
+            (Lang::Ru, true) => {
+                "Время в произвольном тексте, например, «встреча завтра в 15:00», предлагается как напоминание.\nВыключить: /fuzzy off"
+            }
+            (Lang::Ru, false) => {
+                "Время в произвольном тексте не ищется. Включите /fuzzy on, чтобы «встреча завтра в 15:00» предлагалась как напоминание"
+            }
+        }
+    }
+
+    pub fn fuzzy_set(self, enabled: bool) -> &'static str {
+        match (self, enabled) {
+            (Lang::En, true) => "Time phrases in free text will be offered as reminders.",
+            (Lang::En, false) => "Free text is no longer searched for time phrases.",
+            (Lang::Ru, true) => "Время в произвольном тексте будет предлагаться как напоминание.",
+            (Lang::Ru, false) => "Время в произвольном тексте больше не ищется.",
+        }
+    }
+
+    pub fn unknown_fuzzy(self, input: &str) -> String {
+        match self {
+            Lang::En => format!("Unknown option: {input}. Use /fuzzy on or /fuzzy off."),
+            Lang::Ru => {
+                format!("Неизвестный параметр: {input}. Используйте /fuzzy on или /fuzzy off.")
+            }
+        }
+    }
+
+    // Confirmations and listings ---------------------------------------------
+
+    /// Event time in the zone written in the command and in the user's one.
+    pub fn zone_times(
+        self,
+        event_time: &str,
+        event_zone: &str,
+        user_time: &str,
+        user_zone: &str,
+    ) -> String {
+        match self {
+            Lang::En => {
+                format!("{event_time} {event_zone} is {user_time} in your zone {user_zone}")
+            }
+            Lang::Ru => {
+                format!("{event_time} {event_zone} - это {user_time} в вашем поясе {user_zone}")
+            }
+        }
+    }
+
+    pub fn event_in_past(self) -> &'static str {
+        match self {
+            Lang::En => "Event time is in the past. Is it right?",
+            Lang::Ru => "Время события уже прошло. Все верно?",
+        }
+    }
+
+    pub fn remind_today(self, time: &str) -> String {
+        match self {
+            Lang::En => format!("I'll remind you today at {time}"),
+            Lang::Ru => format!("Напомню сегодня в {time}"),
+        }
+    }
+
+    pub fn remind_tomorrow(self, time: &str) -> String {
+        match self {
+            Lang::En => format!("I'll remind you tomorrow at {time}"),
+            Lang::Ru => format!("Напомню завтра в {time}"),
+        }
+    }
+
+    pub fn remind_on(self, date: NaiveDate, order: DateOrder, time: &str) -> String {
+        let date = self.long_date(date, order);
+        match self {
+            Lang::En => format!("I'll remind you {date} at {time}"),
+            Lang::Ru => format!("Напомню {date} в {time}"),
+        }
+    }
+
+    pub fn repeats(self, rule: &str) -> String {
+        match self {
+            Lang::En => format!("Repeats {rule}"),
+            Lang::Ru => format!("Повторяется {rule}"),
+        }
+    }
+
+    pub fn ends(self, end: &str) -> String {
+        match self {
+            Lang::En => format!("Ends {end}"),
+            Lang::Ru => format!("Закончится {end}"),
+        }
+    }
+
+    /// Lead alerts in the confirmation, `1h, 15m`.
+    pub fn leads(self, leads: &str) -> String {
+        match self {
+            Lang::En => format!("Also {leads} before"),
+            Lang::Ru => format!("Заранее: за {leads}"),
+        }
+    }
+
+    /// Alerts that would already be in the past, `2d`.
+    pub fn leads_too_late(self, leads: &str) -> String {
+        match self {
+            Lang::En => format!("Too late to remind {leads} before"),
+            Lang::Ru => format!("Слишком поздно напоминать за {leads}"),
+        }
+    }
+
+    /// Text of a lead alert, `In 15m: standup`.
+    pub fn lead_alert(self, lead: &str, text: &str) -> String {
+        match self {
+            Lang::En => format!("In {lead}: {text}"),
+            Lang::Ru => format!("Через {lead}: {text}"),
+        }
+    }
+
+    /// `after 3 reminders`
+    pub fn ends_after(self, count: u32) -> String {
+        match self {
+            Lang::En if count == 1 => "after 1 reminder".to_owned(),
+            Lang::En => format!("after {count} reminders"),
+            Lang::Ru => format!(
+                "после {count} {}",
+                plural_ru(count, "напоминания", "напоминаний", "напоминаний")
+            ),
+        }
+    }
+
+    /// `on 31 Dec 2026`
+    pub fn ends_on(self, date: NaiveDate, order: DateOrder) -> String {
+        let date = format!("{} {}", self.short_date(date, order), date.year());
+        match self {
+            Lang::En => format!("on {date}"),
+            Lang::Ru => date,
+        }
+    }
+
+    pub fn or(self) -> &'static str {
+        match self {
+            Lang::En => " or ",
+            Lang::Ru => " или ",
+        }
+    }
+
+    /// Part of a listing line, `since 18 Oct 9.30`.
+    pub fn since(self, start: &str) -> String {
+        match self {
+            Lang::En => format!("since {start}"),
+            Lang::Ru => format!("с {start}"),
+        }
+    }
+
+    /// Part of a listing line, `, ends after 3 reminders`.
+    pub fn list_ends(self, end: &str) -> String {
+        match self {
+            Lang::En => format!(", ends {end}"),
+            Lang::Ru => format!(", закончится {end}"),
+        }
+    }
+
+    /// Part of a listing line, `, also 15m before`.
+    pub fn list_leads(self, leads: &str) -> String {
+        match self {
+            Lang::En => format!(", also {leads} before"),
+            Lang::Ru => format!(", заранее за {leads}"),
+        }
+    }
+
+    pub fn edit_header(self) -> &'static str {
+        match self {
+            Lang::En => "Choose a reminder to edit:\n",
+            Lang::Ru => "Выберите напоминание, которое нужно изменить:\n",
+        }
+    }
+
+    pub fn expect_edit(self, text: &str) -> String {
+        match self {
+            Lang::En => format!(
+                "Editing «{text}». Send the new reminder, e.g. at 18 call mom or rep 9 1d tea. Only a time (at 18) keeps the text, only a text keeps the time"
+            ),
+            Lang::Ru => format!(
+                "Изменяем «{text}». Пришлите новое напоминание, например, в 18 позвонить маме или rep 9 1d чай. Только время (в 18) оставит текст, только текст оставит время"
+            ),
+        }
+    }
+
+    pub fn edit_event_gone(self) -> &'static str {
+        match self {
+            Lang::En => "This reminder has already fired or was deleted, see /list",
+            Lang::Ru => "Это напоминание уже сработало или удалено, см. /list",
+        }
+    }
+
+    pub fn no_active_events(self) -> &'static str {
+        match self {
+            Lang::En => "No current active event",
+            Lang::Ru => "Нет активных событий",
+        }
+    }
+
+    pub fn scheduled_summary(self, scheduled: usize, total: usize) -> String {
+        match self {
+            Lang::En => format!("Scheduled {scheduled} of {total} reminders:"),
+            Lang::Ru => format!("Запланировано напоминаний: {scheduled} из {total}"),
+        }
+    }
+
+    pub fn skipped(self, line: &str, error: &str) -> String {
+        match self {
+            Lang::En => format!("Skipped \"{line}\": {error}"),
+            Lang::Ru => format!("Пропущено \"{line}\": {error}"),
+        }
+    }
+
+    // Parse errors -----------------------------------------------------------
+
+    pub fn parse_error(self, error: &ParseError) -> String {
+        match self {
+            Lang::En => parse_error_en(error),
+            Lang::Ru => parse_error_ru(error),
+        }
+    }
+}
+
+/// Last day of `month`, used in hints for days missing in short months.
+fn last_day_of_month(year: i32, month: u32) -> Option<u32> {
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|first| first.checked_add_months(chrono::Months::new(1)))
+        .and_then(|next| next.pred_opt())
+        .map(|last| last.day())
+}
+
+fn parse_error_en(error: &ParseError) -> String {
+    match error {
+        ParseError::NoMatch => "Not a time spec".to_owned(),
+        ParseError::InvalidNumber(number) => format!("Number {number} is too big"),
+        ParseError::InvalidMonth { day, month, order } => {
+            let mut text = format!(
+                "There is no month {month}, dates are written as {} (see /date_order)",
+                Lang::En.date_pattern(*order)
+            );
+            if (1..=12).contains(day) && *month <= 31 {
+                text += &format!(". Did you mean {}?", order.format(*month, *day));
+            }
+            text
+        }
+        ParseError::InvalidDay {
+            day,
+            month,
+            year,
+            order,
+        } => {
+            let mut text = format!("There is no day {day} in month {month}");
+            if let Some(last_day) = last_day_of_month(*year, *month) {
+                text += &format!(". Did you mean {}?", order.format(last_day, *month));
+            }
+            text
+        }
+        ParseError::HourOutOfRange { hour, am_pm: true } => format!(
+            "Hour {hour} doesn't fit am/pm, use 1-12 (e.g. 7pm) or 24-hour time (e.g. 19.00)"
+        ),
+        ParseError::HourOutOfRange { hour: 24, .. } => {
+            "Hour 24 is out of range 0-23. Did you mean 0.00?".to_owned()
+        }
+        ParseError::HourOutOfRange { hour, .. } => {
+            format!("Hour {hour} is out of range 0-23, e.g. at 18.30")
+        }
+        ParseError::MinuteOutOfRange(minute) => {
+            format!("Minute {minute} is out of range 0-59, e.g. at 18.30")
+        }
+        ParseError::InvalidOffset(offset) => {
+            format!("Unknown UTC offset {offset}, use e.g. +03:00 or Z")
+        }
+        ParseError::MissingText => {
+            "What should I remind you about? Add the text after the time, e.g. at 18.30 call mom"
+                .to_owned()
+        }
+        ParseError::UnknownUnit(unit) => {
+            format!(
+                "Unknown time unit \"{unit}\", use w, d, h, m, s, mo, y (or н, д, ч, м, с, мес, г), e.g. 1h30m tea"
+            )
+        }
+        ParseError::ZeroDuration => "Duration should be longer than zero, e.g. 15m tea".to_owned(),
+        ParseError::UnknownRule(rule) => format!(
+            "Unknown repeat rule \"{rule}\", use a period (7d), a calendar rule (1mo, 1y, last, 2tue) or weekdays (mon,wed)"
+        ),
+        ParseError::UnknownWeekday(day) => {
+            format!("Unknown day \"{day}\", use mon-sun, пн-вс, weekday or weekend")
+        }
+        ParseError::InvalidCron(expression) => format!(
+            "Cron expression \"{expression}\" is invalid or never matches, use 5 fields: minute hour day month weekday, e.g. \"0 9 * * 1-5\""
+        ),
+        ParseError::ZeroCount => "Number of reminders should be at least 1, e.g. x10".to_owned(),
+        ParseError::EndsBeforeStart => {
+            "The series ends before its first reminder, check the until date".to_owned()
+        }
+        ParseError::InvalidLead(lead) => format!(
+            "Alert \"{lead}\" should be in weeks, days, hours or minutes before the time, e.g. at 14 -15m standup"
+        ),
+    }
+}
+
+fn parse_error_ru(error: &ParseError) -> String {
+    match error {
+        ParseError::NoMatch => "Это не похоже на время".to_owned(),
+        ParseError::InvalidNumber(number) => format!("Слишком большое число {number}"),
+        ParseError::InvalidMonth { day, month, order } => {
+            let mut text = format!(
+                "Нет месяца {month}, дата пишется как {} (см. /date_order)",
+                Lang::Ru.date_pattern(*order)
+            );
+            if (1..=12).contains(day) && *month <= 31 {
+                text += &format!(". Может быть, {}?", order.format(*month, *day));
+            }
+            text
+        }
+        ParseError::InvalidDay {
+            day,
+            month,
+            year,
+            order,
+        } => {
+            let mut text = format!("В месяце {month} нет {day}-го числа");
+            if let Some(last_day) = last_day_of_month(*year, *month) {
+                text += &format!(". Может быть, {}?", order.format(last_day, *month));
+            }
+            text
+        }
+        ParseError::HourOutOfRange { hour, am_pm: true } => format!(
+            "Час {hour} не подходит для am/pm, используйте 1-12 (например, 7pm) или 24-часовое время (например, 19.00)"
+        ),
+        ParseError::HourOutOfRange { hour: 24, .. } => {
+            "Час 24 вне диапазона 0-23. Может быть, 0.00?".to_owned()
+        }
+        ParseError::HourOutOfRange { hour, .. } => {
+            format!("Час {hour} вне диапазона 0-23, например, в 18.30")
+        }
+        ParseError::MinuteOutOfRange(minute) => {
+            format!("Минута {minute} вне диапазона 0-59, например, в 18.30")
+        }
+        ParseError::InvalidOffset(offset) => {
+            format!("Неизвестное смещение от UTC {offset}, используйте, например, +03:00 или Z")
+        }
+        ParseError::MissingText => {
+            "О чем напомнить? Добавьте текст после времени, например, в 18.30 позвонить маме"
+                .to_owned()
+        }
+        ParseError::UnknownUnit(unit) => format!(
+            "Неизвестная единица времени \"{unit}\", используйте н, д, ч, м, с, мес, г (или w, d, h, m, s, mo, y), например, 1ч30м чай"
+        ),
+        ParseError::ZeroDuration => {
+            "Длительность должна быть больше нуля, например, 15м чай".to_owned()
+        }
+        ParseError::UnknownRule(rule) => format!(
+            "Неизвестное правило повтора \"{rule}\", используйте период (7d), календарное правило (1mo, 1y, last, 2tue) или дни недели (пн,ср)"
+        ),
+        ParseError::UnknownWeekday(day) => {
+            format!("Неизвестный день \"{day}\", используйте пн-вс, mon-sun, будни или выходные")
+        }
+        ParseError::InvalidCron(expression) => format!(
+            "Cron-выражение \"{expression}\" неверно или никогда не сработает, нужно 5 полей: минута час день месяц день_недели, например, \"0 9 * * 1-5\""
+        ),
+        ParseError::ZeroCount => "Напоминаний должно быть хотя бы одно, например, x10".to_owned(),
+        ParseError::EndsBeforeStart => {
+            "Серия заканчивается раньше первого напоминания, проверьте дату окончания".to_owned()
+        }
+        ParseError::InvalidLead(lead) => format!(
+            "Заблаговременное напоминание \"{lead}\" задается в неделях, днях, часах или минутах, например, в 14 -15м планерка"
+        ),
+    }
+}
+
 // Command list to BotFather to copy-paste
 /*
 list - list of all reminders
 help - view help
 at - "at" command helper
 delete_rep - delete repetitive event
+timezone - view or change your time zone
+language - change interface language
+date_order - choose DD-MM or MM-DD dates
+fuzzy - find reminders in free text, on or off
+nag - how reminders starting with ! are repeated
+quiet - set quiet hours without reminders
+digest - daily list of the day's reminders
+edit - change a reminder
+parse - show how a reminder is read without saving it
 */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_from_code() {
+        assert_eq!(Lang::from_code(Some("ru")), Lang::Ru);
+        assert_eq!(Lang::from_code(Some("ru-RU")), Lang::Ru);
+        assert_eq!(Lang::from_code(Some("en-US")), Lang::En);
+        assert_eq!(Lang::from_code(Some("de")), Lang::En);
+        assert_eq!(Lang::from_code(None), Lang::En);
+        for lang in LANGUAGES {
+            assert_eq!(Lang::from_code(Some(lang.code())), *lang);
+        }
+    }
+
+    #[test]
+    fn localized_dates() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 8).unwrap();
+        let (dmy, mdy) = (DateOrder::DayMonth, DateOrder::MonthDay);
+        assert_eq!(Lang::En.short_date(date, dmy), "8 Oct");
+        assert_eq!(Lang::En.short_date(date, mdy), "Oct 8");
+        assert_eq!(Lang::Ru.short_date(date, mdy), "8 окт");
+        assert_eq!(Lang::En.long_date(date, dmy), "8 October");
+        assert_eq!(Lang::En.long_date(date, mdy), "October 8");
+        assert_eq!(Lang::Ru.long_date(date, dmy), "8 октября");
+        assert_eq!(
+            Lang::Ru.short_datetime(date.and_hms_opt(9, 30, 0).unwrap(), dmy),
+            "8 окт 9.30"
+        );
+        assert_eq!(Lang::Ru.month_name(10), "Октябрь");
+    }
+
+    #[test]
+    fn russian_plural() {
+        let forms = |n| plural_ru(n, "день", "дня", "дней");
+        assert_eq!(forms(1), "день");
+        assert_eq!(forms(3), "дня");
+        assert_eq!(forms(5), "дней");
+        assert_eq!(forms(11), "дней");
+        assert_eq!(forms(21), "день");
+        assert_eq!(forms(112), "дней");
+    }
+}
@@ -0,0 +1,239 @@
+use chrono::prelude::*;
+use chrono::{Duration, LocalResult};
+use chrono_tz::Tz;
+use regex::Regex;
+use std::sync::LazyLock;
+use tzf_rs::DefaultFinder;
+
+/// Zone assigned to users that have not chosen one yet.
+pub const DEFAULT_TIMEZONE: Tz = chrono_tz::Europe::Moscow;
+
+/// Resolve a wall-clock time in `tz` to an instant.
+///
+/// Around DST switches the local time may happen twice (clock set back) or
+/// not at all (clock jumped forward). In the first case the earlier instant
+/// is used, in the second the offset in effect before the jump is kept, so
+/// 02:30 inside a 02:00-03:00 gap becomes 03:30.
+pub fn local_to_utc(tz: &Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(t) => t.with_timezone(&Utc),
+        LocalResult::Ambiguous(earliest, _) => earliest.with_timezone(&Utc),
+        LocalResult::None => {
+            let before = tz
+                .offset_from_utc_datetime(&(local - Duration::days(1)))
+                .fix();
+            Utc.from_utc_datetime(&(local - Duration::seconds(before.local_minus_utc() as i64)))
+        }
+    }
+}
+
+/// Parse a zone typed by user: IANA name (case insensitive) or UTC offset
+/// like `+3`, `UTC-5`, `GMT+05:30`.
+pub fn parse_timezone(input: &str, now: DateTime<Utc>) -> Option<Tz> {
+    let input = input.trim();
+    if let Ok(tz) = Tz::from_str_insensitive(input) {
+        return Some(tz);
+    }
+    let offset = parse_utc_offset(input)?;
+    fixed_offset_zone(offset, now.year())
+}
+
+// common abbreviations, each names a fixed offset, so `PST` in summer is
+// still UTC-8; ambiguous ones like `CST` or `IST` are left out
+const ZONE_ABBREVIATIONS: &[(&str, Tz)] = &[
+    ("UTC", chrono_tz::UTC),
+    ("GMT", chrono_tz::Etc::GMT),
+    ("WET", chrono_tz::Etc::GMT),
+    ("BST", chrono_tz::Etc::GMTMinus1),
+    ("CET", chrono_tz::Etc::GMTMinus1),
+    ("CEST", chrono_tz::Etc::GMTMinus2),
+    ("EET", chrono_tz::Etc::GMTMinus2),
+    ("EEST", chrono_tz::Etc::GMTMinus3),
+    ("MSK", chrono_tz::Etc::GMTMinus3),
+    ("SGT", chrono_tz::Etc::GMTMinus8),
+    ("HKT", chrono_tz::Etc::GMTMinus8),
+    ("JST", chrono_tz::Etc::GMTMinus9),
+    ("KST", chrono_tz::Etc::GMTMinus9),
+    ("AEST", chrono_tz::Etc::GMTMinus10),
+    ("AEDT", chrono_tz::Etc::GMTMinus11),
+    ("NZST", chrono_tz::Etc::GMTMinus12),
+    ("NZDT", chrono_tz::Etc::GMTMinus13),
+    ("EST", chrono_tz::Etc::GMTPlus5),
+    ("EDT", chrono_tz::Etc::GMTPlus4),
+    ("CDT", chrono_tz::Etc::GMTPlus5),
+    ("MST", chrono_tz::Etc::GMTPlus7),
+    ("MDT", chrono_tz::Etc::GMTPlus6),
+    ("PST", chrono_tz::Etc::GMTPlus8),
+    ("PDT", chrono_tz::Etc::GMTPlus7),
+];
+
+/// Zone written right after the time of a reminder, `at 10 UTC call`.
+/// Stricter than `parse_timezone`, since the word may be the event text:
+/// IANA names need a slash (`Europe/London`), abbreviations are upper case
+/// (`UTC`, `PST`) and offsets carry minutes or a prefix (`+05:30`, `UTC+3`).
+pub fn parse_zone_suffix(word: &str, now: DateTime<Utc>) -> Option<Tz> {
+    if word.contains('/') {
+        return Tz::from_str_insensitive(word).ok();
+    }
+    if let Some((_, tz)) = ZONE_ABBREVIATIONS.iter().find(|(name, _)| *name == word) {
+        return Some(*tz);
+    }
+    static REG: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"^(?:(?i:utc|gmt)[+-]\d{1,2}(?::?\d{2})?|[+-]\d{2}:?\d{2})$").unwrap()
+    });
+    if !REG.is_match(word) {
+        return None;
+    }
+    fixed_offset_zone(parse_utc_offset(word)?, now.year())
+}
+
+/// Zone covering the point, looked up in the embedded tz-boundary data.
+pub fn timezone_by_location(latitude: f64, longitude: f64) -> Option<Tz> {
+    // unpacking boundaries is costly, do it once on first use
+    static FINDER: LazyLock<DefaultFinder> = LazyLock::new(DefaultFinder::new);
+    FINDER.get_tz_name(longitude, latitude).parse().ok()
+}
+
+/// Zone name with its current UTC offset, e.g. `Europe/Berlin (UTC+02:00)`.
+pub fn describe_timezone(tz: &Tz, now: DateTime<Utc>) -> String {
+    format!("{} (UTC{})", tz.name(), now.with_timezone(tz).format("%:z"))
+}
+
+/// Offset in seconds east of UTC.
+fn parse_utc_offset(input: &str) -> Option<i32> {
+    static REG: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"^(?i:utc|gmt)?\s*(?P<sign>[+-])(?P<hour>\d{1,2})(?::?(?P<minute>\d{2}))?$")
+            .unwrap()
+    });
+    let cap = REG.captures(input)?;
+    let hour: i32 = cap.name("hour").unwrap().as_str().parse().unwrap();
+    let minute: i32 = cap
+        .name("minute")
+        .map_or(0, |c| c.as_str().parse().unwrap());
+    if hour > 14 || minute >= 60 {
+        return None;
+    }
+    let offset = hour * 3600 + minute * 60;
+    Some(if &cap["sign"] == "-" { -offset } else { offset })
+}
+
+/// Find a zone which keeps `offset` the whole `year`.
+fn fixed_offset_zone(offset: i32, year: i32) -> Option<Tz> {
+    if offset % 3600 == 0 {
+        // POSIX style names: Etc/GMT-3 is three hours *ahead* of UTC
+        let name = format!("Etc/GMT{:+}", -offset / 3600);
+        if let Ok(tz) = name.parse() {
+            return Some(tz);
+        }
+    }
+    let probes = [
+        Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).unwrap(),
+        Utc.with_ymd_and_hms(year, 7, 1, 0, 0, 0).unwrap(),
+    ];
+    chrono_tz::TZ_VARIANTS.iter().copied().find(|tz| {
+        probes
+            .iter()
+            .all(|t| t.with_timezone(tz).offset().fix().local_minus_utc() == offset)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::{Asia, Europe};
+
+    fn naive(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn zone_suffix() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 9, 0, 0).unwrap();
+        let zone = |word| parse_zone_suffix(word, now);
+        assert_eq!(zone("Europe/London"), Some(Europe::London));
+        assert_eq!(zone("america/new_york"), Some(chrono_tz::America::New_York));
+        assert_eq!(zone("UTC"), Some(chrono_tz::UTC));
+        // fixed offsets, whatever the season
+        assert_eq!(zone("PST"), Some(chrono_tz::Etc::GMTPlus8));
+        assert_eq!(zone("PDT"), Some(chrono_tz::Etc::GMTPlus7));
+        let india = zone("+05:30").unwrap();
+        assert_eq!(
+            now.with_timezone(&india).offset().fix().local_minus_utc(),
+            19800
+        );
+        assert_eq!(zone("UTC+3"), Some(chrono_tz::Etc::GMTMinus3));
+        // plain words, numbers and ambiguous abbreviations are the event text
+        for word in ["utc", "call", "Poland", "+5", "2", "OK", "CST", "IST"] {
+            assert_eq!(zone(word), None, "{word}");
+        }
+    }
+
+    #[test]
+    fn half_hour_zone() {
+        let t = local_to_utc(&Asia::Kolkata, naive(2026, 1, 10, 9, 0));
+        assert_eq!(t, Utc.with_ymd_and_hms(2026, 1, 10, 3, 30, 0).unwrap());
+    }
+
+    #[test]
+    fn summer_and_winter_offsets() {
+        let winter = local_to_utc(&Europe::Berlin, naive(2026, 1, 10, 9, 0));
+        let summer = local_to_utc(&Europe::Berlin, naive(2026, 7, 10, 9, 0));
+        assert_eq!(winter, Utc.with_ymd_and_hms(2026, 1, 10, 8, 0, 0).unwrap());
+        assert_eq!(summer, Utc.with_ymd_and_hms(2026, 7, 10, 7, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn nonexistent_time_moves_forward() {
+        // 2026-03-29 02:00 CET clocks jump to 03:00 CEST
+        let t = local_to_utc(&Europe::Berlin, naive(2026, 3, 29, 2, 30));
+        assert_eq!(t, Utc.with_ymd_and_hms(2026, 3, 29, 1, 30, 0).unwrap());
+        assert_eq!(t.with_timezone(&Europe::Berlin).hour(), 3);
+    }
+
+    #[test]
+    fn ambiguous_time_takes_earliest() {
+        // 2026-10-25 03:00 CEST clocks go back to 02:00 CET
+        let t = local_to_utc(&Europe::Berlin, naive(2026, 10, 25, 2, 30));
+        assert_eq!(t, Utc.with_ymd_and_hms(2026, 10, 25, 0, 30, 0).unwrap());
+    }
+
+    #[test]
+    fn parse_timezone_input() {
+        let now = Utc.with_ymd_and_hms(2026, 6, 1, 0, 0, 0).unwrap();
+        let expect = [
+            ("Europe/Berlin", Some(Europe::Berlin)),
+            ("europe/berlin", Some(Europe::Berlin)),
+            ("UTC", Some(chrono_tz::UTC)),
+            ("+3", Some(chrono_tz::Etc::GMTMinus3)),
+            ("UTC-5", Some(chrono_tz::Etc::GMTPlus5)),
+            ("utc+00:00", Some(chrono_tz::Etc::GMTPlus0)),
+            ("Mars/Olympus", None),
+            ("+15", None),
+            ("+03:61", None),
+        ];
+        for (input, tz) in expect {
+            assert_eq!(parse_timezone(input, now), tz, "{input}");
+        }
+
+        let tz = parse_timezone("+05:30", now).unwrap();
+        assert_eq!(
+            describe_timezone(&tz, now),
+            format!("{} (UTC+05:30)", tz.name())
+        );
+        let tz = parse_timezone("UTC+5:45", now).unwrap();
+        assert_eq!(now.with_timezone(&tz).format("%:z").to_string(), "+05:45");
+    }
+
+    #[test]
+    fn timezone_from_location() {
+        assert_eq!(timezone_by_location(52.52, 13.405), Some(Europe::Berlin));
+        assert_eq!(timezone_by_location(28.61, 77.21), Some(Asia::Kolkata));
+        assert_eq!(
+            timezone_by_location(40.71, -74.0),
+            Some(chrono_tz::America::New_York)
+        );
+    }
+}